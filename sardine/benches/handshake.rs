@@ -0,0 +1,65 @@
+//! Handshake latency per key size.
+//!
+//! Criterion baselines compare revisions: run `cargo bench --bench handshake -- --save-baseline before` on the
+//! reference revision, then `cargo bench --bench handshake -- --baseline before` on the one to evaluate.
+
+#[macro_use]
+extern crate criterion;
+extern crate sardine;
+
+use std::sync::Arc;
+
+use criterion::{BenchmarkId, Criterion};
+use sardine::{DhGroup, KeyExchange, Srd, SrdConfig};
+
+fn run_handshake(client_config: &Arc<SrdConfig>, server_config: &Arc<SrdConfig>) {
+    let mut client = Srd::with_config(false, client_config.clone());
+    let mut server = Srd::with_config(true, server_config.clone());
+
+    let mut client_out = Vec::new();
+    let mut server_out = Vec::new();
+    client.authenticate(&[], &mut client_out).unwrap();
+    while !server.state().is_complete() {
+        server.authenticate(&client_out, &mut server_out).unwrap();
+        client_out.clear();
+        if !client.state().is_complete() {
+            client.authenticate(&server_out, &mut client_out).unwrap();
+        }
+        server_out.clear();
+    }
+}
+
+fn finite_field(c: &mut Criterion) {
+    let mut group = c.benchmark_group("handshake/finite_field");
+    group.sample_size(10);
+
+    for key_size in [256u16, 512, 1024].iter() {
+        let client_config = SrdConfig::builder()
+            .key_size(*key_size)
+            .key_exchanges(vec![KeyExchange::FiniteField])
+            .skip_delegation(true)
+            .build()
+            .unwrap();
+        let server_config = SrdConfig::builder()
+            .key_size(*key_size)
+            .dh_group(DhGroup::legacy(*key_size).unwrap())
+            .skip_delegation(true)
+            .build()
+            .unwrap();
+        let (client_config, server_config) = (Arc::new(client_config), Arc::new(server_config));
+
+        group.bench_with_input(BenchmarkId::from_parameter(key_size), key_size, |b, _| {
+            b.iter(|| run_handshake(&client_config, &server_config))
+        });
+    }
+
+    group.finish();
+}
+
+fn x25519(c: &mut Criterion) {
+    let config = Arc::new(SrdConfig::builder().skip_delegation(true).build().unwrap());
+    c.bench_function("handshake/x25519", |b| b.iter(|| run_handshake(&config, &config)));
+}
+
+criterion_group!(benches, finite_field, x25519);
+criterion_main!(benches);
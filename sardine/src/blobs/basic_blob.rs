@@ -1,12 +1,15 @@
+use std::fmt;
 use std::io::Read;
 use std::io::Write;
 
+use zeroize::{Zeroize, Zeroizing};
+
 use blobs::Blob;
 use messages::Message;
 use srd_errors::SrdError;
 use Result;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(PartialEq, Eq, Clone)]
 pub struct BasicBlob {
     username: String,
     password: String,
@@ -19,7 +22,31 @@ impl BasicBlob {
             password: password.to_string(),
         }
     }
+
+    pub fn get_username(&self) -> String {
+        self.username.clone()
+    }
+
+    pub fn get_password(&self) -> String {
+        self.password.clone()
+    }
+}
+
+impl fmt::Debug for BasicBlob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BasicBlob")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+impl Drop for BasicBlob {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
 }
+
 impl Blob for BasicBlob {
     fn blob_type() -> &'static str {
         "Basic"
@@ -31,9 +58,9 @@ impl Message for BasicBlob {
     where
         Self: Sized,
     {
-        let mut str_buffer = Vec::new();
+        let mut str_buffer = Zeroizing::new(Vec::new());
         reader.read_to_end(&mut str_buffer)?;
-        let full_str = String::from_utf8_lossy(str_buffer.as_slice()).to_string();
+        let full_str = Zeroizing::new(String::from_utf8_lossy(str_buffer.as_slice()).to_string());
 
         let v: Vec<&str> = full_str.split(':').collect();
 
@@ -45,7 +72,9 @@ impl Message for BasicBlob {
     }
 
     fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
-        let mut full_str = self.username.clone();
+        // Sized upfront, a reallocation would leave a copy of the password behind
+        let mut full_str = Zeroizing::new(String::with_capacity(self.username.len() + 1 + self.password.len()));
+        full_str.push_str(&self.username);
         full_str.push_str(":");
         full_str.push_str(&self.password);
         writer.write_all(full_str.as_bytes())?;
@@ -1,12 +1,15 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::fmt;
 use std::io::Read;
 use std::io::Write;
 
+use zeroize::{Zeroize, Zeroizing};
+
 use blobs::Blob;
 use messages::Message;
 use Result;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(PartialEq, Eq, Clone)]
 pub struct LogonBlob {
     username: String,
     password: String,
@@ -29,6 +32,21 @@ impl LogonBlob {
     }
 }
 
+impl fmt::Debug for LogonBlob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LogonBlob")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+impl Drop for LogonBlob {
+    fn drop(&mut self) {
+        self.password.zeroize();
+    }
+}
+
 impl Blob for LogonBlob {
     fn blob_type() -> &'static str {
         "Logon"
@@ -48,10 +66,10 @@ impl Message for LogonBlob {
         reader.read_u8()?;
         let username: String = String::from_utf8_lossy(username_buf.as_slice()).to_string();
 
-        let mut password_buf = vec![0u8; password_length as usize];
+        let mut password_buf = Zeroizing::new(vec![0u8; password_length as usize]);
         reader.read_exact(&mut password_buf)?;
         reader.read_u8()?;
-        let password: String = String::from_utf8_lossy(password_buf.as_slice()).to_string();
+        let password = Zeroizing::new(String::from_utf8_lossy(password_buf.as_slice()).to_string());
 
         Ok(LogonBlob::new(&username, &password))
     }
@@ -7,6 +7,7 @@ use Result;
 
 use rand::rngs::OsRng;
 use rand::RngCore;
+use zeroize::Zeroize;
 
 mod basic_blob;
 mod logon_blob;
@@ -56,6 +57,13 @@ impl SrdBlob {
     }
 }
 
+impl Drop for SrdBlob {
+    fn drop(&mut self) {
+        // Blobs carry credentials
+        self.data.zeroize();
+    }
+}
+
 impl SrdBlob {
     pub fn blob_type(&self) -> &str {
         &self.blob_type
@@ -64,6 +72,15 @@ impl SrdBlob {
     pub fn data(&self) -> &[u8] {
         &self.data
     }
+
+    /// Decode the blob as a `T`, if it is one.
+    pub fn get<T: Blob>(&self) -> Result<Option<T>> {
+        if self.blob_type != T::blob_type() {
+            return Ok(None);
+        }
+        let mut cursor = std::io::Cursor::new(&self.data);
+        Ok(Some(T::read_from(&mut cursor)?))
+    }
 }
 
 impl Message for SrdBlob {
@@ -126,7 +143,7 @@ pub trait Blob: Message {
 
 #[cfg(test)]
 mod test {
-    use blobs::SrdBlob;
+    use blobs::{BasicBlob, LogonBlob, SrdBlob};
     use messages::Message;
     use std;
 
@@ -148,4 +165,15 @@ mod test {
             Err(_) => assert!(false),
         };
     }
+
+    #[test]
+    fn passwords_are_redacted() {
+        let basic = format!("{:?}", BasicBlob::new("alice", "hunter2"));
+        let logon = format!("{:?}", LogonBlob::new("alice", "hunter2"));
+
+        for debug in [basic, logon].iter() {
+            assert!(debug.contains("alice"));
+            assert!(!debug.contains("hunter2"));
+        }
+    }
 }
@@ -1,10 +1,14 @@
 use srd_errors::SrdError;
 
 use chacha::{ChaCha, KeyStream};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
+use zeroize::Zeroize;
 
 cfg_if! {
     if #[cfg(feature = "aes")]{
         use aes::Aes256;
+        use aes_gcm::Aes256Gcm;
         use block_modes::{BlockMode, Cbc, block_padding::NoPadding};
     }
 }
@@ -12,31 +16,61 @@ cfg_if! {
 use Result;
 
 const AES256_FLAG: u32 = 0x00000001;
+const AES256_GCM_FLAG: u32 = 0x00000002;
 const CHACHA20_FLAG: u32 = 0x00000100;
 const XCHACHA20_FLAG: u32 = 0x00000200;
+const CHACHA20_POLY1305_FLAG: u32 = 0x00000400;
+const XCHACHA20_POLY1305_FLAG: u32 = 0x00000800;
 
-#[derive(Clone, Copy, Eq, PartialEq)]
+const TAG_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
 pub enum Cipher {
+    /// AES-256 in CBC mode, without padding
     AES256,
     ChaCha20,
     XChaCha20,
+    AES256GCM,
+    ChaCha20Poly1305,
+    XChaCha20Poly1305,
 }
 
 impl Cipher {
+    /// Encrypt `data`. AEAD ciphers append their authentication tag to the result.
     pub fn encrypt_data(&self, data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
         match self {
             &Cipher::AES256 => encrypt_data_aes(data, key, iv),
             &Cipher::ChaCha20 => encrypt_data_chacha(data, key, iv),
             &Cipher::XChaCha20 => encrypt_data_xchacha(data, key, iv),
+            &Cipher::AES256GCM => encrypt_data_aes_gcm(data, key, iv),
+            &Cipher::ChaCha20Poly1305 => seal(ChaCha20Poly1305::new_from_slice(key), data, &iv[0..12]),
+            &Cipher::XChaCha20Poly1305 => seal(XChaCha20Poly1305::new_from_slice(key), data, &iv[0..24]),
         }
     }
 
+    /// Decrypt `data`. AEAD ciphers verify the tag first and don't return anything from tampered data.
     pub fn decrypt_data(&self, data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
         match self {
             &Cipher::AES256 => decrypt_data_aes(data, key, iv),
             &Cipher::ChaCha20 => encrypt_data_chacha(data, key, iv),
             &Cipher::XChaCha20 => encrypt_data_xchacha(data, key, iv),
+            &Cipher::AES256GCM => decrypt_data_aes_gcm(data, key, iv),
+            &Cipher::ChaCha20Poly1305 => open(ChaCha20Poly1305::new_from_slice(key), data, &iv[0..12]),
+            &Cipher::XChaCha20Poly1305 => open(XChaCha20Poly1305::new_from_slice(key), data, &iv[0..24]),
+        }
+    }
+
+    /// Whether the cipher authenticates what it encrypts.
+    pub fn is_aead(&self) -> bool {
+        self.tag_size() != 0
+    }
+
+    /// Size of the authentication tag added to encrypted data.
+    pub fn tag_size(&self) -> usize {
+        match self {
+            &Cipher::AES256 | &Cipher::ChaCha20 | &Cipher::XChaCha20 => 0,
+            &Cipher::AES256GCM | &Cipher::ChaCha20Poly1305 | &Cipher::XChaCha20Poly1305 => TAG_SIZE,
         }
     }
 
@@ -45,6 +79,9 @@ impl Cipher {
             &Cipher::AES256 => AES256_FLAG,
             &Cipher::ChaCha20 => CHACHA20_FLAG,
             &Cipher::XChaCha20 => XCHACHA20_FLAG,
+            &Cipher::AES256GCM => AES256_GCM_FLAG,
+            &Cipher::ChaCha20Poly1305 => CHACHA20_POLY1305_FLAG,
+            &Cipher::XChaCha20Poly1305 => XCHACHA20_POLY1305_FLAG,
         }
     }
 
@@ -53,16 +90,34 @@ impl Cipher {
         if flags & AES256_FLAG != 0 {
             ciphers.push(Cipher::AES256)
         };
+        if flags & AES256_GCM_FLAG != 0 {
+            ciphers.push(Cipher::AES256GCM)
+        };
         if flags & CHACHA20_FLAG != 0 {
             ciphers.push(Cipher::ChaCha20)
         };
         if flags & XCHACHA20_FLAG != 0 {
             ciphers.push(Cipher::XChaCha20)
         };
+        if flags & CHACHA20_POLY1305_FLAG != 0 {
+            ciphers.push(Cipher::ChaCha20Poly1305)
+        };
+        if flags & XCHACHA20_POLY1305_FLAG != 0 {
+            ciphers.push(Cipher::XChaCha20Poly1305)
+        };
         ciphers
     }
 
     pub fn best_cipher(ciphers: &[Cipher]) -> Result<Cipher> {
+        if ciphers.contains(&Cipher::XChaCha20Poly1305) {
+            return Ok(Cipher::XChaCha20Poly1305);
+        };
+        if ciphers.contains(&Cipher::ChaCha20Poly1305) {
+            return Ok(Cipher::ChaCha20Poly1305);
+        };
+        if ciphers.contains(&Cipher::AES256GCM) {
+            return Ok(Cipher::AES256GCM);
+        };
         if ciphers.contains(&Cipher::XChaCha20) {
             return Ok(Cipher::XChaCha20);
         };
@@ -100,6 +155,26 @@ fn decrypt_data_aes(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
     Ok(plaintext)
 }
 
+#[cfg(feature = "aes")]
+fn encrypt_data_aes_gcm(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    seal(Aes256Gcm::new_from_slice(key), data, &iv[0..12])
+}
+
+#[cfg(feature = "aes")]
+fn decrypt_data_aes_gcm(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    open(Aes256Gcm::new_from_slice(key), data, &iv[0..12])
+}
+
+#[cfg(not(feature = "aes"))]
+fn encrypt_data_aes_gcm(_: &[u8], _: &[u8], _: &[u8]) -> Result<Vec<u8>> {
+    unreachable!();
+}
+
+#[cfg(not(feature = "aes"))]
+fn decrypt_data_aes_gcm(_: &[u8], _: &[u8], _: &[u8]) -> Result<Vec<u8>> {
+    unreachable!();
+}
+
 #[cfg(not(feature = "aes"))]
 fn encrypt_data_aes(_: &[u8], _: &[u8], _: &[u8]) -> Result<Vec<u8>> {
     unreachable!();
@@ -118,6 +193,7 @@ fn encrypt_data_chacha(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
     iv_ref.copy_from_slice(&iv[0..8]);
 
     let mut stream = ChaCha::new_chacha20(&key_ref, &iv_ref);
+    key_ref.zeroize();
     let mut buffer = data.to_vec();
 
     stream.xor_read(&mut buffer)?;
@@ -132,8 +208,57 @@ fn encrypt_data_xchacha(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
     iv_ref.copy_from_slice(&iv[0..24]);
 
     let mut stream = ChaCha::new_xchacha20(&key_ref, &iv_ref);
+    key_ref.zeroize();
     let mut buffer = data.to_vec();
 
     stream.xor_read(&mut buffer)?;
     Ok(buffer)
 }
+
+fn seal<A: Aead, E>(cipher: std::result::Result<A, E>, data: &[u8], nonce: &[u8]) -> Result<Vec<u8>> {
+    let cipher = cipher.map_err(|_| SrdError::Crypto)?;
+    cipher.encrypt(nonce.into(), data).map_err(|_| SrdError::Crypto)
+}
+
+fn open<A: Aead, E>(cipher: std::result::Result<A, E>, data: &[u8], nonce: &[u8]) -> Result<Vec<u8>> {
+    let cipher = cipher.map_err(|_| SrdError::Crypto)?;
+    cipher.decrypt(nonce.into(), data).map_err(|_| SrdError::InvalidMac)
+}
+
+#[cfg(test)]
+mod test {
+    use cipher::Cipher;
+
+    #[test]
+    fn aead_ciphers_authenticate() {
+        let mut ciphers = vec![Cipher::ChaCha20Poly1305, Cipher::XChaCha20Poly1305];
+        if cfg!(feature = "aes") {
+            ciphers.push(Cipher::AES256GCM);
+        }
+
+        let key = [7u8; 32];
+        let iv = [9u8; 32];
+        for cipher in ciphers {
+            let encrypted = cipher.encrypt_data(b"delegated blob", &key, &iv).unwrap();
+            assert_eq!(encrypted.len(), 14 + cipher.tag_size());
+            assert_eq!(cipher.decrypt_data(&encrypted, &key, &iv).unwrap(), b"delegated blob");
+
+            let mut tampered = encrypted.clone();
+            tampered[0] ^= 1;
+            assert!(cipher.decrypt_data(&tampered, &key, &iv).is_err());
+            assert!(cipher.decrypt_data(&encrypted[..8], &key, &iv).is_err());
+            assert!(cipher.decrypt_data(&encrypted, &[8u8; 32], &iv).is_err());
+        }
+    }
+
+    #[test]
+    fn aead_ciphers_are_preferred() {
+        let ciphers = Cipher::from_flags(0xFFFF_FFFF);
+        assert_eq!(ciphers.len(), 6);
+        assert_eq!(Cipher::best_cipher(&ciphers).unwrap(), Cipher::XChaCha20Poly1305);
+        assert_eq!(
+            Cipher::best_cipher(&[Cipher::AES256, Cipher::AES256GCM, Cipher::XChaCha20]).unwrap(),
+            Cipher::AES256GCM
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use argon2::{Argon2, PasswordVerifier};
+use zeroize::Zeroizing;
+
+use blobs::{BasicBlob, LogonBlob, SrdBlob};
+use credentials::CredentialValidator;
+use messages::DelegationResult;
+use srd_errors::SrdError;
+use Result;
+
+#[derive(Clone)]
+enum PasswordHash {
+    Bcrypt(String),
+    Argon2(String),
+}
+
+impl PasswordHash {
+    fn verify(&self, password: &str) -> bool {
+        match self {
+            PasswordHash::Bcrypt(hash) => bcrypt::verify(password, hash).unwrap_or(false),
+            PasswordHash::Argon2(hash) => match argon2::PasswordHash::new(hash) {
+                Ok(hash) => Argon2::default().verify_password(password.as_bytes(), &hash).is_ok(),
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+/// Validator checking Logon and Basic blobs against an htpasswd file.
+///
+/// Each line holds a user name and the hash of its password separated by a colon, empty lines and lines starting
+/// with `#` are ignored. Hashes are either bcrypt, as written by `htpasswd -B`, or argon2 PHC strings. Files with
+/// other hashes, like the MD5 or SHA-1 ones of htpasswd, are refused.
+pub struct HtpasswdValidator {
+    users: HashMap<String, PasswordHash>,
+    // Checked for unknown users, so that they take as long as known ones
+    dummy_hash: Option<PasswordHash>,
+}
+
+impl HtpasswdValidator {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<HtpasswdValidator> {
+        let content = Zeroizing::new(std::fs::read_to_string(path)?);
+        HtpasswdValidator::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<HtpasswdValidator> {
+        let mut users = HashMap::new();
+        let mut dummy_hash = None;
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid_line = |reason: &str| {
+                SrdError::Io(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("htpasswd line {}: {}", index + 1, reason),
+                ))
+            };
+
+            let (username, hash) = match line.find(':') {
+                Some(colon) => (&line[..colon], &line[colon + 1..]),
+                None => return Err(invalid_line("no password hash")),
+            };
+
+            let hash = if ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|prefix| hash.starts_with(prefix)) {
+                PasswordHash::Bcrypt(hash.to_owned())
+            } else if hash.starts_with("$argon2") && argon2::PasswordHash::new(hash).is_ok() {
+                PasswordHash::Argon2(hash.to_owned())
+            } else {
+                return Err(invalid_line("unsupported password hash"));
+            };
+
+            if dummy_hash.is_none() {
+                dummy_hash = Some(hash.clone());
+            }
+            users.insert(username.to_owned(), hash);
+        }
+
+        Ok(HtpasswdValidator { users, dummy_hash })
+    }
+
+    /// Whether the password is the one of the user. Unknown users have no valid password.
+    ///
+    /// The password of an unknown user is still checked, against the first hash of the file, so that the time taken
+    /// doesn't tell which users exist.
+    pub fn verify(&self, username: &str, password: &str) -> bool {
+        match self.users.get(username) {
+            Some(hash) => hash.verify(password),
+            None => {
+                if let Some(ref dummy_hash) = self.dummy_hash {
+                    dummy_hash.verify(password);
+                }
+                false
+            }
+        }
+    }
+}
+
+impl CredentialValidator for HtpasswdValidator {
+    fn validate(&self, blob: &SrdBlob) -> Result<DelegationResult> {
+        let (username, password) = if let Some(logon) = blob.get::<LogonBlob>()? {
+            (logon.get_username(), Zeroizing::new(logon.get_password()))
+        } else if let Some(basic) = blob.get::<BasicBlob>()? {
+            (basic.get_username(), Zeroizing::new(basic.get_password()))
+        } else {
+            // No credentials this validator knows about
+            return Ok(DelegationResult::failure(Vec::new()));
+        };
+
+        if self.verify(&username, &password) {
+            Ok(DelegationResult::success(Vec::new()))
+        } else {
+            Ok(DelegationResult::failure(Vec::new()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    use argon2::{Algorithm, Argon2, Params, Version};
+
+    use blobs::{BasicBlob, Blob, LogonBlob, SrdBlob};
+    use credentials::{CredentialValidator, HtpasswdValidator};
+    use messages::Message;
+
+    fn srd_blob<T: Blob>(blob: T) -> SrdBlob {
+        let mut data = Vec::new();
+        blob.write_to(&mut data).unwrap();
+        SrdBlob::new(T::blob_type(), &data)
+    }
+
+    fn htpasswd() -> String {
+        let bcrypt_hash = bcrypt::hash("1234567", 4).unwrap();
+
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::new(256, 1, 1, None).unwrap());
+        let salt = SaltString::from_b64("c2FyZGluZXNhbHQ").unwrap();
+        let argon2_hash = argon2.hash_password(b"hunter2", &salt).unwrap().to_string();
+
+        format!("# Test users\nfdubois:{}\n\nalice:{}\n", bcrypt_hash, argon2_hash)
+    }
+
+    #[test]
+    fn htpasswd_hashes() {
+        let validator = HtpasswdValidator::parse(&htpasswd()).unwrap();
+
+        assert!(validator.verify("fdubois", "1234567"));
+        assert!(!validator.verify("fdubois", "7654321"));
+        assert!(validator.verify("alice", "hunter2"));
+        assert!(!validator.verify("alice", "1234567"));
+        assert!(!validator.verify("bob", "1234567"));
+        assert!(!validator.verify("bob", "hunter2"));
+
+        // MD5 and SHA-1 hashes aren't supported, nor lines without a hash
+        assert!(HtpasswdValidator::parse("fdubois:$apr1$salt$hash").is_err());
+        assert!(HtpasswdValidator::parse("fdubois:{SHA}hash").is_err());
+        assert!(HtpasswdValidator::parse("fdubois").is_err());
+    }
+
+    #[test]
+    fn htpasswd_validates_blobs() {
+        let validator = HtpasswdValidator::parse(&htpasswd()).unwrap();
+
+        let result = validator.validate(&srd_blob(LogonBlob::new("fdubois", "1234567"))).unwrap();
+        assert!(result.is_success());
+        let result = validator.validate(&srd_blob(BasicBlob::new("alice", "hunter2"))).unwrap();
+        assert!(result.is_success());
+        let result = validator.validate(&srd_blob(LogonBlob::new("alice", "1234567"))).unwrap();
+        assert!(!result.is_success());
+        let result = validator.validate(&SrdBlob::new("Custom", &[0, 1, 2, 3])).unwrap();
+        assert!(!result.is_success());
+    }
+}
@@ -0,0 +1,78 @@
+//! Credentials delegated by a client, provided once the server is authenticated and validated by the server.
+
+use blobs::SrdBlob;
+use cipher::Cipher;
+use messages::DelegationResult;
+use protocol_version::ProtocolVersion;
+use Result;
+
+cfg_if! {
+    if #[cfg(feature = "htpasswd")] {
+        mod htpasswd;
+        pub use self::htpasswd::HtpasswdValidator;
+    }
+}
+
+/// Decides whether the blob delegated by a client holds valid credentials.
+///
+/// A server `Srd` with a validator calls it as soon as the Delegate message is decrypted. Typed blobs are read
+/// with `SrdBlob::get`. When the handshake has a Result message, the verdict is sent to the client in it and the
+/// handshake completes either way, the refusal is then read with `get_delegation_result`. Otherwise a refused blob
+/// fails the handshake with `SrdError::InvalidCredentials`. An error fails the handshake with that error.
+pub trait CredentialValidator: Send + Sync {
+    fn validate(&self, blob: &SrdBlob) -> Result<DelegationResult>;
+}
+
+/// Builds the blob a client delegates, once the server has proven it holds the shared secret.
+///
+/// A client `Srd` with a provider calls it when the Confirm message has a valid MAC and CBT, right before the
+/// Delegate message is encrypted, so credentials don't have to be known before the handshake starts. An error
+/// fails the handshake with that error.
+pub trait BlobProvider: Send + Sync {
+    fn provide(&self, context: &DelegationContext) -> Result<SrdBlob>;
+}
+
+/// What was negotiated with an authenticated server, given to a `BlobProvider`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegationContext {
+    cipher: Cipher,
+    use_cbt: bool,
+    version: ProtocolVersion,
+    accepted_blob_types: Vec<String>,
+}
+
+impl DelegationContext {
+    pub(crate) fn new(
+        cipher: Cipher,
+        use_cbt: bool,
+        version: ProtocolVersion,
+        accepted_blob_types: Vec<String>,
+    ) -> DelegationContext {
+        DelegationContext {
+            cipher,
+            use_cbt,
+            version,
+            accepted_blob_types,
+        }
+    }
+
+    /// Cipher the Delegate message is encrypted with.
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    /// Whether the server was bound to the channel with a CBT.
+    pub fn use_cbt(&self) -> bool {
+        self.use_cbt
+    }
+
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Blob types the server advertised in the Confirm message, empty when it accepts any blob or doesn't say.
+    /// A blob of another type fails the handshake with `SrdError::UnsupportedBlobType` before it is sent.
+    pub fn accepted_blob_types(&self) -> &[String] {
+        &self.accepted_blob_types
+    }
+}
@@ -0,0 +1,194 @@
+use dh_params::*;
+
+/// Finite-field Diffie-Hellman group.
+///
+/// Groups are identified on the wire by their `id`. The ffdhe ids are the TLS named group code points, the MODP
+/// ones are the IKE group numbers.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
+pub enum DhGroup {
+    /// RFC 7919 groups
+    Ffdhe2048,
+    Ffdhe3072,
+    Ffdhe4096,
+    /// RFC 3526 groups
+    Modp2048,
+    Modp3072,
+    Modp4096,
+    Modp6144,
+    Modp8192,
+    /// Groups used before the group could be negotiated, selected by key size
+    Legacy2048,
+    Legacy4096,
+    Legacy8192,
+}
+
+static DH_GROUPS: [DhGroup; 11] = [
+    DhGroup::Ffdhe2048,
+    DhGroup::Ffdhe3072,
+    DhGroup::Ffdhe4096,
+    DhGroup::Modp2048,
+    DhGroup::Modp3072,
+    DhGroup::Modp4096,
+    DhGroup::Modp6144,
+    DhGroup::Modp8192,
+    DhGroup::Legacy2048,
+    DhGroup::Legacy4096,
+    DhGroup::Legacy8192,
+];
+
+impl DhGroup {
+    pub fn all() -> &'static [DhGroup] {
+        &DH_GROUPS
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DhGroup::Ffdhe2048 => "ffdhe2048",
+            DhGroup::Ffdhe3072 => "ffdhe3072",
+            DhGroup::Ffdhe4096 => "ffdhe4096",
+            DhGroup::Modp2048 => "modp2048",
+            DhGroup::Modp3072 => "modp3072",
+            DhGroup::Modp4096 => "modp4096",
+            DhGroup::Modp6144 => "modp6144",
+            DhGroup::Modp8192 => "modp8192",
+            DhGroup::Legacy2048 => "legacy2048",
+            DhGroup::Legacy4096 => "legacy4096",
+            DhGroup::Legacy8192 => "legacy8192",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DhGroup> {
+        DH_GROUPS.iter().cloned().find(|group| group.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn id(self) -> u16 {
+        match self {
+            DhGroup::Ffdhe2048 => 0x0100,
+            DhGroup::Ffdhe3072 => 0x0101,
+            DhGroup::Ffdhe4096 => 0x0102,
+            DhGroup::Modp2048 => 14,
+            DhGroup::Modp3072 => 15,
+            DhGroup::Modp4096 => 16,
+            DhGroup::Modp6144 => 17,
+            DhGroup::Modp8192 => 18,
+            DhGroup::Legacy2048 => 0xFF00,
+            DhGroup::Legacy4096 => 0xFF01,
+            DhGroup::Legacy8192 => 0xFF02,
+        }
+    }
+
+    pub fn from_id(id: u16) -> Option<DhGroup> {
+        DH_GROUPS.iter().cloned().find(|group| group.id() == id)
+    }
+
+    /// Legacy group of a key size, as selected by servers that don't identify the group.
+    pub fn legacy(key_size: u16) -> Option<DhGroup> {
+        match key_size {
+            256 => Some(DhGroup::Legacy2048),
+            512 => Some(DhGroup::Legacy4096),
+            1024 => Some(DhGroup::Legacy8192),
+            _ => None,
+        }
+    }
+
+    pub fn is_legacy(self) -> bool {
+        matches!(self, DhGroup::Legacy2048 | DhGroup::Legacy4096 | DhGroup::Legacy8192)
+    }
+
+    /// Size of the prime in bytes.
+    pub fn key_size(self) -> u16 {
+        self.prime().len() as u16
+    }
+
+    /// Size of the private exponents in bytes.
+    pub fn exponent_size(self) -> usize {
+        exponent_size(self.key_size() as usize)
+    }
+
+    pub fn prime(self) -> &'static [u8] {
+        self.params().p_data
+    }
+
+    pub fn generator(self) -> &'static [u8] {
+        self.params().g_data
+    }
+
+    fn params(self) -> &'static SrdDhParams {
+        match self {
+            DhGroup::Ffdhe2048 => &FFDHE_2048,
+            DhGroup::Ffdhe3072 => &FFDHE_3072,
+            DhGroup::Ffdhe4096 => &FFDHE_4096,
+            DhGroup::Modp2048 => &MODP_2048,
+            DhGroup::Modp3072 => &MODP_3072,
+            DhGroup::Modp4096 => &MODP_4096,
+            DhGroup::Modp6144 => &MODP_6144,
+            DhGroup::Modp8192 => &MODP_8192,
+            DhGroup::Legacy2048 => &LEGACY_2048,
+            DhGroup::Legacy4096 => &LEGACY_4096,
+            DhGroup::Legacy8192 => &LEGACY_8192,
+        }
+    }
+}
+
+/// Size in bytes of the private exponents used with a safe prime of `prime_size` bytes.
+///
+/// Exponents have at least the length recommended by RFC 7919, twice the security strength of the group. An
+/// exponent as long as the prime doesn't make the key exchange any stronger.
+pub fn exponent_size(prime_size: usize) -> usize {
+    match prime_size {
+        // 225 bits
+        0..=256 => 29,
+        // 275 bits
+        257..=384 => 35,
+        // 325 bits
+        385..=512 => 41,
+        // 375 bits
+        513..=768 => 47,
+        // 400 bits
+        _ => 50,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dh_group::{exponent_size, DhGroup};
+    use num_bigint::BigUint;
+
+    #[test]
+    fn dh_group_registry() {
+        for group in DhGroup::all() {
+            assert_eq!(DhGroup::from_name(group.name()), Some(*group));
+            assert_eq!(DhGroup::from_id(group.id()), Some(*group));
+            assert_eq!(group.key_size() as usize * 8, group.name()[group.name().len() - 4..].parse::<usize>().unwrap());
+        }
+
+        assert_eq!(DhGroup::from_name("FFDHE3072"), Some(DhGroup::Ffdhe3072));
+        assert_eq!(DhGroup::from_name("ffdhe1024"), None);
+        assert_eq!(DhGroup::legacy(512), Some(DhGroup::Legacy4096));
+    }
+
+    #[test]
+    fn dh_group_exponent_sizes() {
+        assert_eq!(DhGroup::Ffdhe2048.exponent_size(), 29);
+        assert_eq!(DhGroup::Modp6144.exponent_size(), 47);
+        assert_eq!(DhGroup::Legacy8192.exponent_size(), 50);
+        // Custom groups get the size of the next larger standard one
+        assert_eq!(exponent_size(300), 35);
+    }
+
+    #[test]
+    fn dh_group_primes() {
+        // RFC 3526 and RFC 7919 primes have their 64 high and low bits set
+        for group in DhGroup::all().iter().filter(|group| !group.is_legacy()) {
+            let prime = group.prime();
+            assert_eq!(&prime[..8], &[0xFF; 8]);
+            assert_eq!(&prime[prime.len() - 8..], &[0xFF; 8]);
+            assert_eq!(BigUint::from_bytes_be(group.generator()), BigUint::from(2u32));
+        }
+
+        // The legacy 4096 and 8192-bit groups only differ by their generator
+        assert_eq!(DhGroup::Legacy4096.prime(), DhGroup::Modp4096.prime());
+        assert_eq!(DhGroup::Legacy8192.prime(), DhGroup::Modp8192.prime());
+    }
+}
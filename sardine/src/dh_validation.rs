@@ -0,0 +1,254 @@
+use num_bigint::BigUint;
+use num_traits::{One, ToPrimitive, Zero};
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+use dh_group::DhGroup;
+use srd_errors::SrdError;
+use Result;
+
+// Miller-Rabin rounds, for a probability of accepting a composite below 2^-64 whatever the input
+const MILLER_RABIN_ROUNDS: usize = 32;
+
+const SMALL_PRIMES: [u32; 24] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+/// Check the finite-field parameters offered by a server.
+///
+/// Known groups are accepted, provided an identified group comes with its own parameters. Any other group must use
+/// a safe prime. The generator must not be of order 1 or 2.
+pub fn validate_dh_params(group_id: Option<u16>, generator: &BigUint, prime: &BigUint) -> Result<Option<DhGroup>> {
+    let known = DhGroup::all().iter().cloned().find(|group| {
+        &BigUint::from_bytes_be(group.prime()) == prime && &BigUint::from_bytes_be(group.generator()) == generator
+    });
+
+    let group = match (group_id.and_then(DhGroup::from_id), known) {
+        (Some(claimed), Some(known)) if claimed == known => Some(known),
+        // A known id with other parameters than its own
+        (Some(_), _) => return Err(SrdError::UntrustedDhGroup),
+        (None, Some(known)) => Some(known),
+        (None, None) => {
+            if !is_safe_prime(prime)? {
+                return Err(SrdError::UntrustedDhGroup);
+            }
+            None
+        }
+    };
+
+    if !is_in_range(generator, prime) {
+        return Err(SrdError::WeakDhGenerator);
+    }
+
+    Ok(group)
+}
+
+/// Check a public key received from the peer before using it, the prime being a safe prime.
+///
+/// The key must be in 1 < y < p - 1. When the generator is a quadratic residue, it generates the subgroup of order
+/// q = (p - 1) / 2 and so must the key.
+pub fn validate_public_key(public_key: &BigUint, generator: &BigUint, prime: &BigUint) -> Result<()> {
+    if !is_in_range(public_key, prime) {
+        return Err(SrdError::PublicKeyOutOfRange);
+    }
+
+    // For a safe prime, the elements of the subgroup of order q are the quadratic residues
+    if jacobi(generator, prime) == 1 && jacobi(public_key, prime) != 1 {
+        return Err(SrdError::PublicKeyNotInSubgroup);
+    }
+
+    Ok(())
+}
+
+fn is_in_range(value: &BigUint, prime: &BigUint) -> bool {
+    let one = BigUint::one();
+    value > &one && prime > &one && value < &(prime - &one)
+}
+
+fn is_safe_prime(prime: &BigUint) -> Result<bool> {
+    if prime <= &BigUint::from(7u32) || !is_odd(prime) {
+        return Ok(false);
+    }
+
+    let q: BigUint = prime >> 1;
+    for small_prime in SMALL_PRIMES.iter() {
+        let small_prime = BigUint::from(*small_prime);
+        if (prime % &small_prime).is_zero() || (&q % &small_prime).is_zero() {
+            return Ok(false);
+        }
+    }
+
+    if !is_probable_prime(&q)? {
+        return Ok(false);
+    }
+
+    // Pocklington: with q prime and p - 1 = 2q, p is prime if 2^(p - 1) = 1 mod p
+    let p_minus_one = prime - BigUint::one();
+    Ok(BigUint::from(2u32).modpow(&p_minus_one, prime).is_one())
+}
+
+// Miller-Rabin with random bases, n being odd and greater than 7
+fn is_probable_prime(n: &BigUint) -> Result<bool> {
+    let one = BigUint::one();
+    let n_minus_one = n - &one;
+
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while !is_odd(&d) {
+        d >>= 1;
+        s += 1;
+    }
+
+    let mut bytes = vec![0u8; n.to_bytes_be().len()];
+    for _ in 0..MILLER_RABIN_ROUNDS {
+        // Base in [2, n - 2]
+        OsRng.try_fill_bytes(&mut bytes)?;
+        let base = BigUint::from_bytes_be(&bytes) % (n - 3u32) + 2u32;
+
+        let mut x = base.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+
+        let mut composite = true;
+        for _ in 1..s {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                composite = false;
+                break;
+            }
+        }
+
+        if composite {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// Jacobi symbol (a/n) for an odd n
+fn jacobi(a: &BigUint, n: &BigUint) -> i8 {
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut result = 1;
+
+    while !a.is_zero() {
+        while !is_odd(&a) {
+            a >>= 1;
+            let r = low_bits(&n) & 7;
+            if r == 3 || r == 5 {
+                result = -result;
+            }
+        }
+
+        std::mem::swap(&mut a, &mut n);
+        if low_bits(&a) & 3 == 3 && low_bits(&n) & 3 == 3 {
+            result = -result;
+        }
+        a = &a % &n;
+    }
+
+    if n.is_one() {
+        result
+    } else {
+        0
+    }
+}
+
+fn is_odd(value: &BigUint) -> bool {
+    low_bits(value) & 1 == 1
+}
+
+fn low_bits(value: &BigUint) -> u8 {
+    (value % 256u32).to_u8().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use dh_group::DhGroup;
+    use dh_validation::{jacobi, validate_dh_params, validate_public_key};
+    use num_bigint::BigUint;
+    use srd_errors::SrdErrorKind;
+
+    fn group_params(group: DhGroup) -> (BigUint, BigUint) {
+        (BigUint::from_bytes_be(group.generator()), BigUint::from_bytes_be(group.prime()))
+    }
+
+    #[test]
+    fn known_dh_groups() {
+        for group in DhGroup::all() {
+            let (generator, prime) = group_params(*group);
+            assert_eq!(validate_dh_params(Some(group.id()), &generator, &prime).unwrap(), Some(*group));
+        }
+
+        // Legacy offers don't carry the group id
+        let (generator, prime) = group_params(DhGroup::Legacy4096);
+        assert_eq!(validate_dh_params(None, &generator, &prime).unwrap(), Some(DhGroup::Legacy4096));
+
+        // A known id can't come with other parameters
+        let error = validate_dh_params(Some(DhGroup::Ffdhe2048.id()), &generator, &prime).unwrap_err();
+        assert_eq!(error.kind(), SrdErrorKind::UntrustedDhGroup);
+    }
+
+    #[test]
+    fn untrusted_dh_groups() {
+        let (generator, prime) = group_params(DhGroup::Ffdhe2048);
+
+        // Composite
+        let composite = &prime * BigUint::from(3u32);
+        let error = validate_dh_params(None, &generator, &composite).unwrap_err();
+        assert_eq!(error.kind(), SrdErrorKind::UntrustedDhGroup);
+
+        // Prime, but not a safe one: 2^127 - 1
+        let mersenne = (BigUint::from(1u32) << 127) - BigUint::from(1u32);
+        let error = validate_dh_params(Some(0x1234), &generator, &mersenne).unwrap_err();
+        assert_eq!(error.kind(), SrdErrorKind::UntrustedDhGroup);
+
+        // Past trial division, (p - 1) / 2 = 101 * 103
+        let error = validate_dh_params(None, &generator, &BigUint::from(20807u32)).unwrap_err();
+        assert_eq!(error.kind(), SrdErrorKind::UntrustedDhGroup);
+
+        // Unknown group using a safe prime, 1019 = 2 * 509 + 1
+        let safe_prime = BigUint::from(1019u32);
+        assert_eq!(validate_dh_params(Some(0x1234), &generator, &safe_prime).unwrap(), None);
+
+        // Generators of order 1 and 2
+        for weak in [BigUint::from(1u32), BigUint::from(1018u32)].iter() {
+            let error = validate_dh_params(Some(0x1234), weak, &safe_prime).unwrap_err();
+            assert_eq!(error.kind(), SrdErrorKind::WeakDhGenerator);
+        }
+    }
+
+    #[test]
+    fn public_key_validation() {
+        let (generator, prime) = group_params(DhGroup::Ffdhe2048);
+        let public_key = generator.modpow(&BigUint::from(12345u32), &prime);
+        assert!(validate_public_key(&public_key, &generator, &prime).is_ok());
+
+        for invalid in [BigUint::from(0u32), BigUint::from(1u32), &prime - BigUint::from(1u32), prime.clone()].iter() {
+            let error = validate_public_key(invalid, &generator, &prime).unwrap_err();
+            assert_eq!(error.kind(), SrdErrorKind::PublicKeyOutOfRange);
+        }
+
+        // 2 generates the subgroup of order q, -2 is outside of it
+        let outside = &prime - BigUint::from(2u32);
+        let error = validate_public_key(&outside, &generator, &prime).unwrap_err();
+        assert_eq!(error.kind(), SrdErrorKind::PublicKeyNotInSubgroup);
+
+        // The legacy 2048-bit generator spans the whole group, keys of both subgroups are valid
+        let (generator, prime) = group_params(DhGroup::Legacy2048);
+        assert!(validate_public_key(&(&prime - BigUint::from(2u32)), &generator, &prime).is_ok());
+    }
+
+    #[test]
+    fn jacobi_symbol() {
+        let n = BigUint::from(23u32);
+        let residues = [1u32, 2, 3, 4, 6, 8, 9, 12, 13, 16, 18];
+        for a in 1..23u32 {
+            let expected = if residues.contains(&a) { 1 } else { -1 };
+            assert_eq!(jacobi(&BigUint::from(a), &n), expected);
+        }
+        assert_eq!(jacobi(&BigUint::from(46u32), &n), 0);
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use zeroize::Zeroize;
+
+use dh_group::DhGroup;
+use key_exchange::dh_key_pair;
+use srd_errors::SrdError;
+use Result;
+
+/// Server key pairs generated ahead of time by a worker thread, one queue per finite-field group.
+///
+/// The worker refills a group as soon as it drops below the low watermark, up to the high watermark. A pair is
+/// removed from the pool when it is taken so it is never used twice. Handshakes finding the pool empty compute
+/// their own pair.
+pub struct EphemeralKeyPool {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    state: Mutex<PoolState>,
+    refill: Condvar,
+    low_watermark: usize,
+    high_watermark: usize,
+}
+
+// Private and public keys
+type KeyPair = (Vec<u8>, Vec<u8>);
+
+struct PoolState {
+    pairs: HashMap<DhGroup, VecDeque<KeyPair>>,
+    shutdown: bool,
+}
+
+impl EphemeralKeyPool {
+    /// Start a pool filling key pairs for `groups`.
+    ///
+    /// The low watermark must be at least 1 and no larger than the high watermark.
+    pub fn new(groups: &[DhGroup], low_watermark: usize, high_watermark: usize) -> Result<EphemeralKeyPool> {
+        if low_watermark == 0 || low_watermark > high_watermark {
+            return Err(SrdError::Internal("Invalid key pool watermarks".to_owned()));
+        }
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(PoolState {
+                pairs: groups.iter().map(|group| (*group, VecDeque::new())).collect(),
+                shutdown: false,
+            }),
+            refill: Condvar::new(),
+            low_watermark,
+            high_watermark,
+        });
+
+        let worker_shared = shared.clone();
+        let worker = thread::Builder::new()
+            .name("srd-key-pool".to_owned())
+            .spawn(move || fill(&worker_shared))?;
+
+        Ok(EphemeralKeyPool {
+            shared,
+            worker: Some(worker),
+        })
+    }
+
+    /// Take a private and public key pair of a group, if one is ready.
+    pub fn take(&self, group: DhGroup) -> Option<(Vec<u8>, Vec<u8>)> {
+        let mut state = self.shared.lock();
+        let pairs = state.pairs.get_mut(&group)?;
+        let pair = pairs.pop_front();
+
+        if pairs.len() < self.shared.low_watermark {
+            self.shared.refill.notify_one();
+        }
+        pair
+    }
+
+    /// Number of key pairs of a group ready to be taken.
+    pub fn available(&self, group: DhGroup) -> usize {
+        self.shared.lock().pairs.get(&group).map_or(0, VecDeque::len)
+    }
+}
+
+impl Drop for EphemeralKeyPool {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.refill.notify_one();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+
+        for pairs in self.shared.lock().pairs.values_mut() {
+            for (private_key, _) in pairs.iter_mut() {
+                private_key.zeroize();
+            }
+        }
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+fn fill(shared: &Shared) {
+    let mut state = shared.lock();
+    loop {
+        if state.shutdown {
+            return;
+        }
+
+        let group = state
+            .pairs
+            .iter()
+            .find(|(_, pairs)| pairs.len() < shared.low_watermark)
+            .map(|(group, _)| *group);
+
+        let group = match group {
+            Some(group) => group,
+            None => {
+                state = shared.refill.wait(state).unwrap_or_else(|error| error.into_inner());
+                continue;
+            }
+        };
+
+        // Pairs are generated without holding the lock, handshakes keep taking them meanwhile
+        while !state.shutdown && state.pairs[&group].len() < shared.high_watermark {
+            drop(state);
+            let pair = dh_key_pair(group);
+            state = shared.lock();
+
+            match pair {
+                Ok(pair) => state.pairs.get_mut(&group).expect("groups are fixed").push_back(pair),
+                // Handshakes compute their own pairs from now on
+                Err(_) => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use dh_group::DhGroup;
+    use ephemeral_pool::EphemeralKeyPool;
+    use modexp::modpow;
+    use num_bigint::BigUint;
+
+    fn wait_for(pool: &EphemeralKeyPool, group: DhGroup, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        while pool.available(group) < count {
+            assert!(Instant::now() < deadline, "key pool not refilled");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn key_pool_watermarks() {
+        let group = DhGroup::Ffdhe2048;
+        let pool = EphemeralKeyPool::new(&[group], 2, 4).unwrap();
+        wait_for(&pool, group, 4);
+
+        let generator = BigUint::from_bytes_be(group.generator());
+        let prime = BigUint::from_bytes_be(group.prime());
+
+        let mut private_keys = Vec::new();
+        for _ in 0..2 {
+            let (private_key, public_key) = pool.take(group).unwrap();
+            assert_eq!(public_key, modpow(&generator, &private_key, &prime).unwrap());
+            private_keys.push(private_key);
+        }
+
+        // Still at the low watermark, nothing to refill yet
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(pool.available(group), 2);
+
+        // Below it, the pool goes back up to the high watermark
+        private_keys.push(pool.take(group).unwrap().0);
+        wait_for(&pool, group, 4);
+        private_keys.extend((0..4).map(|_| pool.take(group).unwrap().0));
+
+        // Pairs are never handed out twice
+        private_keys.sort();
+        private_keys.dedup();
+        assert_eq!(private_keys.len(), 7);
+
+        assert!(pool.take(DhGroup::Ffdhe3072).is_none());
+    }
+
+    #[test]
+    fn key_pool_validation() {
+        assert!(EphemeralKeyPool::new(&[DhGroup::Ffdhe2048], 0, 4).is_err());
+        assert!(EphemeralKeyPool::new(&[DhGroup::Ffdhe2048], 5, 4).is_err());
+    }
+}
@@ -2,14 +2,16 @@
 
 extern crate libc;
 
-use srd::fill_random;
 use srd::Srd;
 use blobs::SrdBlob;
+use messages::DelegationResult;
 
 use std;
 use std::ptr::copy_nonoverlapping;
 use std::slice;
 
+use zeroize::Zeroizing;
+
 #[no_mangle]
 pub extern "C" fn Srd_New(is_server: bool, skip_delegation: bool) -> *mut Srd {
     Box::into_raw(Box::new(Srd::new(is_server, skip_delegation))) as *mut Srd
@@ -26,19 +28,22 @@ pub extern "C" fn Srd_Input(srd_handle: *mut Srd, buffer: *const u8, buffer_size
     let srd = unsafe { &mut *srd_handle };
 
     let input_data = unsafe { std::slice::from_raw_parts::<u8>(buffer, buffer_size as usize) };
-    let mut output_data = Vec::new();
 
-    match srd.authenticate(&input_data, &mut output_data) {
-        Ok(is_finished) => {
-            srd.set_output_data(output_data);
-            if is_finished {
-                return 0;
+    match srd.step(input_data) {
+        Ok(step) => {
+            srd.set_output_data(step.output_data().map(|data| data.to_vec()).unwrap_or_default());
+            if step.is_complete() {
+                0
             } else {
-                return 1;
+                1
             }
         }
         Err(_) => {
-            return -1;
+            // The alert for the peer, if any, is read with Srd_Output
+            if let Some(alert) = srd.get_alert().map(|alert| alert.to_vec()) {
+                srd.set_output_data(alert);
+            }
+            -1
         }
     }
 }
@@ -147,11 +152,80 @@ pub extern "C" fn Srd_SetCertData(srd_handle: *mut Srd, data: *const u8, data_si
     }
 }
 
+/// Send the delegation result of a server, success when `success` isn't 0. The Result message is then read with
+/// Srd_Output.
+#[no_mangle]
+pub extern "C" fn Srd_SetDelegationResult(
+    srd_handle: *mut Srd,
+    success: libc::c_int,
+    payload: *const u8,
+    payload_size: libc::c_int,
+) -> libc::c_int {
+    let srd = unsafe { &mut *srd_handle };
+    let payload = if payload_size > 0 {
+        unsafe { std::slice::from_raw_parts::<u8>(payload, payload_size as usize) }.to_vec()
+    } else {
+        Vec::new()
+    };
+
+    let result = if success != 0 {
+        DelegationResult::success(payload)
+    } else {
+        DelegationResult::failure(payload)
+    };
+
+    match srd.set_delegation_result(result) {
+        Ok(output_data) => {
+            srd.set_output_data(output_data);
+            1
+        }
+        Err(_) => -1,
+    }
+}
+
+/// 1 if the server accepted the delegated blob, 0 if it didn't and -1 without a delegation result.
+#[no_mangle]
+pub extern "C" fn Srd_DelegationSucceeded(srd_handle: *mut Srd) -> libc::c_int {
+    let srd = unsafe { &mut *srd_handle };
+
+    match srd.get_delegation_result() {
+        Some(result) if result.is_success() => 1,
+        Some(_) => 0,
+        None => -1,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn Srd_GetDelegationResultPayload(
+    srd_handle: *mut Srd,
+    buffer: *mut u8,
+    buffer_size: libc::c_int,
+) -> libc::c_int {
+    let srd = unsafe { &mut *srd_handle };
+
+    if let Some(result) = srd.get_delegation_result() {
+        let payload_len = result.payload().len() as i32;
+
+        if !buffer.is_null() {
+            if payload_len > buffer_size {
+                return -1;
+            }
+
+            let buffer_data = unsafe { std::slice::from_raw_parts_mut::<u8>(buffer, payload_len as usize) };
+            buffer_data.clone_from_slice(result.payload());
+        }
+
+        return payload_len;
+    }
+
+    0
+}
+
 #[no_mangle]
 pub extern "C" fn Srd_GetDelegationKey(srd_handle: *mut Srd, buffer: *mut u8, buffer_size: libc::c_int) -> libc::c_int {
     let srd = unsafe { &mut *srd_handle };
 
-    let key = srd.get_delegation_key();
+    let key = Zeroizing::new(srd.get_delegation_key());
     let size = key.len() as i32;
 
     if buffer != std::ptr::null_mut() {
@@ -170,7 +244,7 @@ pub extern "C" fn Srd_GetDelegationKey(srd_handle: *mut Srd, buffer: *mut u8, bu
 pub extern "C" fn Srd_GetIntegrityKey(srd_handle: *mut Srd, buffer: *mut u8, buffer_size: libc::c_int) -> libc::c_int {
     let srd = unsafe { &mut *srd_handle };
 
-    let key = srd.get_integrity_key();
+    let key = Zeroizing::new(srd.get_integrity_key());
     let size = key.len() as i32;
 
     if buffer != std::ptr::null_mut() {
@@ -185,84 +259,109 @@ pub extern "C" fn Srd_GetIntegrityKey(srd_handle: *mut Srd, buffer: *mut u8, buf
     return size;
 }
 
-const IV_LEN: usize = 32;
-
 #[no_mangle]
-pub extern "C" fn Srd_Encrypt(
+pub extern "C" fn Srd_ExportKeyingMaterial(
     srd_handle: *mut Srd,
-    data: *const u8, 
-    data_size: usize, 
-    output: *mut u8, 
-    output_size: *mut usize
-) -> i32 {
+    label: *const u8,
+    label_size: libc::c_int,
+    context: *const u8,
+    context_size: libc::c_int,
+    buffer: *mut u8,
+    buffer_size: libc::c_int,
+) -> libc::c_int {
     let srd = unsafe { &mut *srd_handle };
-    let key = srd.get_delegation_key();
 
-    if key.iter().all(|&x| x == 0) {
-        return -1
+    if buffer.is_null() || buffer_size <= 0 || label_size < 0 || context_size < 0 {
+        return -1;
     }
 
-    if (data_size % 16) != 0 {
-        return -1
+    let label = if !label.is_null() && label_size > 0 {
+        unsafe { std::slice::from_raw_parts::<u8>(label, label_size as usize) }
+    } else {
+        &[]
+    };
+    let context = if !context.is_null() && context_size > 0 {
+        unsafe { std::slice::from_raw_parts::<u8>(context, context_size as usize) }
+    } else {
+        &[]
+    };
+
+    match srd.export_keying_material(label, context, buffer_size as usize).map(Zeroizing::new) {
+        Ok(material) => {
+            let buffer_data = unsafe { std::slice::from_raw_parts_mut::<u8>(buffer, buffer_size as usize) };
+            buffer_data.clone_from_slice(&material);
+            1
+        }
+        Err(_) => -1,
     }
+}
+
+/// Seal `data` with the session of a completed handshake. The first call starts the session, records must be
+/// decrypted by the peer in the order they are encrypted.
+#[no_mangle]
+pub extern "C" fn Srd_Encrypt(
+    srd_handle: *mut Srd,
+    data: *const u8,
+    data_size: usize,
+    output: *mut u8,
+    output_size: *mut usize
+) -> i32 {
+    let srd = unsafe { &mut *srd_handle };
 
     if output_size.is_null() {
         return -1
     }
 
+    let session = match srd.session_mut() {
+        Ok(session) => session,
+        Err(_) => return -1,
+    };
+
     let available_len = unsafe { *output_size };
-    let required_len: usize = IV_LEN + data_size;
+    let required_len: usize = data_size + session.overhead();
 
     if available_len < required_len || output.is_null() {
         unsafe { *output_size = required_len };
         return 0
     }
 
-    let mut iv = [0u8; IV_LEN];
-    if fill_random(&mut iv).is_err() {
-        return -1
-    }
-
     let data = unsafe { slice::from_raw_parts(data, data_size) };
-    
-    if let Ok(encrypted_data) = srd.get_cipher().encrypt_data(&data, key.as_slice(), &iv) {
-        unsafe {
-            copy_nonoverlapping(iv.as_ptr(), output, IV_LEN);
-            copy_nonoverlapping(encrypted_data.as_ptr(), output.offset(IV_LEN as isize), data_size);
-            *output_size = required_len;
-        }
+    let record = session.seal(data);
 
-        return 1
+    unsafe {
+        copy_nonoverlapping(record.as_ptr(), output, record.len());
+        *output_size = record.len();
     }
 
-    return -1
+    1
 }
 
+/// Open a record sealed by the peer with `Srd_Encrypt`.
 #[no_mangle]
 pub extern "C" fn Srd_Decrypt(
     srd_handle: *mut Srd,
-    data: *const u8, 
-    data_size: usize, 
-    output: *mut u8, 
+    data: *const u8,
+    data_size: usize,
+    output: *mut u8,
     output_size: *mut usize
 ) -> i32 {
     let srd = unsafe { &mut *srd_handle };
-    let key = srd.get_delegation_key();
 
-    if key.iter().all(|&x| x == 0) {
+    if output_size.is_null() {
         return -1
     }
 
-    if data_size < IV_LEN {
-        return -1
-    }
+    let session = match srd.session_mut() {
+        Ok(session) => session,
+        Err(_) => return -1,
+    };
 
-    if output_size.is_null() {
+    if data_size < session.overhead() {
         return -1
     }
-    
+
     let available_len = unsafe { *output_size };
-    let required_len: usize = data_size - IV_LEN;
+    let required_len: usize = data_size - session.overhead();
 
     if available_len < required_len || output.is_null() {
         unsafe { *output_size = required_len };
@@ -270,16 +369,15 @@ pub extern "C" fn Srd_Decrypt(
     }
 
     let data = unsafe { slice::from_raw_parts(data, data_size) };
-    let iv = &data[0..IV_LEN - 1];
 
-    if let Ok(decrypted_data) = srd.get_cipher().decrypt_data(&data[IV_LEN..], key.as_slice(), &iv) {
+    if let Ok(decrypted_data) = session.open(data).map(Zeroizing::new) {
         unsafe {
-            copy_nonoverlapping(decrypted_data.as_ptr(), output, required_len);
-            *output_size = required_len;
+            copy_nonoverlapping(decrypted_data.as_ptr(), output, decrypted_data.len());
+            *output_size = decrypted_data.len();
         }
 
         return 1
     }
 
     return -1
-}
\ No newline at end of file
+}
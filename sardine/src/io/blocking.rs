@@ -0,0 +1,240 @@
+use std;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use io::{frame, frame_length, timed_out, HandshakeOutcome, FRAME_HEADER_SIZE};
+use messages::DelegationResult;
+use srd::Srd;
+use srd_errors::SrdError;
+use srd_state::{SrdState, SrdStep};
+use Result;
+
+/// Streams able to bound the time spent in a single blocking read or write.
+///
+/// Implemented for sockets. Streams without timeouts, like pipes, can be wrapped in a `Pipe`.
+pub trait Timeouts {
+    fn read_timeout(&self) -> std::io::Result<Option<Duration>>;
+    fn write_timeout(&self) -> std::io::Result<Option<Duration>>;
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()>;
+    fn set_write_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()>;
+}
+
+impl Timeouts for TcpStream {
+    fn read_timeout(&self) -> std::io::Result<Option<Duration>> {
+        TcpStream::read_timeout(self)
+    }
+
+    fn write_timeout(&self) -> std::io::Result<Option<Duration>> {
+        TcpStream::write_timeout(self)
+    }
+
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_write_timeout(self, timeout)
+    }
+}
+
+#[cfg(unix)]
+impl Timeouts for std::os::unix::net::UnixStream {
+    fn read_timeout(&self) -> std::io::Result<Option<Duration>> {
+        std::os::unix::net::UnixStream::read_timeout(self)
+    }
+
+    fn write_timeout(&self) -> std::io::Result<Option<Duration>> {
+        std::os::unix::net::UnixStream::write_timeout(self)
+    }
+
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+        std::os::unix::net::UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn set_write_timeout(&mut self, timeout: Option<Duration>) -> std::io::Result<()> {
+        std::os::unix::net::UnixStream::set_write_timeout(self, timeout)
+    }
+}
+
+/// Transport made of a reader and a writer without timeouts, like the standard input and output of a process.
+///
+/// The timeouts from the `Srd` configuration, handshake timeout included, can't be enforced on a pipe and are
+/// ignored.
+#[derive(Debug)]
+pub struct Pipe<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R: Read, W: Write> Pipe<R, W> {
+    pub fn new(reader: R, writer: W) -> Pipe<R, W> {
+        Pipe { reader, writer }
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<R: Read, W> Read for Pipe<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R, W: Write> Write for Pipe<R, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<R, W> Timeouts for Pipe<R, W> {
+    fn read_timeout(&self) -> std::io::Result<Option<Duration>> {
+        Ok(None)
+    }
+
+    fn write_timeout(&self) -> std::io::Result<Option<Duration>> {
+        Ok(None)
+    }
+
+    fn set_read_timeout(&mut self, _timeout: Option<Duration>) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn set_write_timeout(&mut self, _timeout: Option<Duration>) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run a complete client handshake over a blocking stream.
+///
+/// The timeouts from the `Srd` configuration are applied to the stream for the duration of the handshake, the
+/// previous timeouts are restored afterwards.
+pub fn handshake_client<S: Read + Write + Timeouts>(stream: &mut S, srd: &mut Srd) -> Result<HandshakeOutcome> {
+    handshake(stream, srd, Some(Vec::new()))
+}
+
+/// Run a complete server handshake over a blocking stream. See `handshake_client`.
+///
+/// When the client asked for a delegation result, it returns once the delegated blob has been received and the
+/// handshake is completed by `send_delegation_result`.
+pub fn handshake_server<S: Read + Write + Timeouts>(stream: &mut S, srd: &mut Srd) -> Result<HandshakeOutcome> {
+    handshake(stream, srd, None)
+}
+
+/// Send the Result message of a server handshake waiting for its delegation result.
+pub fn send_delegation_result<S: Write + Timeouts>(
+    stream: &mut S,
+    srd: &mut Srd,
+    result: DelegationResult,
+) -> Result<()> {
+    let message = srd._set_delegation_result(result)?;
+
+    let write_timeout = stream.write_timeout()?;
+    let result = write_frame(stream, srd, None, &message);
+    stream.set_write_timeout(write_timeout)?;
+    result
+}
+
+fn handshake<S: Read + Write + Timeouts>(
+    stream: &mut S,
+    srd: &mut Srd,
+    input: Option<Vec<u8>>,
+) -> Result<HandshakeOutcome> {
+    let read_timeout = stream.read_timeout()?;
+    let write_timeout = stream.write_timeout()?;
+
+    let result = run(stream, srd, input);
+
+    stream.set_read_timeout(read_timeout)?;
+    stream.set_write_timeout(write_timeout)?;
+
+    result?;
+    Ok(HandshakeOutcome::new(srd))
+}
+
+fn run<S: Read + Write + Timeouts>(stream: &mut S, srd: &mut Srd, mut input: Option<Vec<u8>>) -> Result<()> {
+    let deadline = srd.config().handshake_timeout().map(|timeout| Instant::now() + timeout);
+
+    loop {
+        let message = match input.take() {
+            Some(message) => message,
+            None => read_frame(stream, srd, deadline)?,
+        };
+
+        let step = srd.step(&message).inspect_err(|_| {
+            // The alert is a courtesy to the peer, the handshake has already failed whether it is sent or not
+            if let Some(alert) = srd.get_alert() {
+                let _ = write_frame(stream, srd, deadline, alert);
+            }
+        })?;
+
+        match step {
+            SrdStep::Send(output) => write_frame(stream, srd, deadline, &output)?,
+            SrdStep::SendAndComplete(output) => {
+                write_frame(stream, srd, deadline, &output)?;
+                return Ok(());
+            }
+            SrdStep::Complete { .. } => return Ok(()),
+            // The application decides on the result, from the outcome
+            SrdStep::Continue if srd.state() == SrdState::AwaitingDelegationResult => return Ok(()),
+            SrdStep::Continue => {}
+        }
+    }
+}
+
+fn read_frame<S: Read + Timeouts>(stream: &mut S, srd: &Srd, deadline: Option<Instant>) -> Result<Vec<u8>> {
+    let mut header = [0u8; FRAME_HEADER_SIZE];
+    stream.set_read_timeout(timeout(srd.config().read_timeout(), deadline)?)?;
+    stream.read_exact(&mut header).map_err(map_timeout)?;
+
+    let mut message = vec![0u8; frame_length(&header, srd.config().max_message_size())?];
+    stream.set_read_timeout(timeout(srd.config().read_timeout(), deadline)?)?;
+    stream.read_exact(&mut message).map_err(map_timeout)?;
+
+    Ok(message)
+}
+
+fn write_frame<S: Write + Timeouts>(
+    stream: &mut S,
+    srd: &Srd,
+    deadline: Option<Instant>,
+    message: &[u8],
+) -> Result<()> {
+    stream.set_write_timeout(timeout(srd.config().write_timeout(), deadline)?)?;
+    stream.write_all(&frame(message)).map_err(map_timeout)?;
+    stream.flush().map_err(map_timeout)?;
+    Ok(())
+}
+
+// Shortest of the operation timeout and the time left for the whole handshake
+fn timeout(timeout: Option<Duration>, deadline: Option<Instant>) -> Result<Option<Duration>> {
+    let remaining = match deadline {
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(timed_out());
+            }
+            Some(deadline - now)
+        }
+        None => None,
+    };
+
+    Ok(match (timeout, remaining) {
+        (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+        (timeout, remaining) => timeout.or(remaining),
+    })
+}
+
+// Sockets report an expired timeout as WouldBlock on some platforms
+fn map_timeout(error: std::io::Error) -> SrdError {
+    match error.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => timed_out(),
+        _ => error.into(),
+    }
+}
@@ -0,0 +1,155 @@
+//! Handshake drivers running the whole SRD exchange over a byte stream.
+//!
+//! Every message is sent as a frame made of its length (u32, little endian) followed by the message itself.
+
+use std::fmt;
+
+use byteorder::{ByteOrder, LittleEndian};
+use zeroize::Zeroize;
+
+use blobs::{Blob, SrdBlob};
+use cipher::Cipher;
+use dh_group::DhGroup;
+use key_exchange::KeyExchange;
+use key_schedule::export_keying_material;
+use messages::DelegationResult;
+use protocol_version::ProtocolVersion;
+use srd::Srd;
+use srd_errors::SrdError;
+use Result;
+
+mod blocking;
+
+pub use self::blocking::{handshake_client, handshake_server, send_delegation_result, Pipe, Timeouts};
+
+cfg_if! {
+    if #[cfg(feature = "tokio")] {
+        mod stream;
+        pub use self::stream::SrdStream;
+    }
+}
+
+const FRAME_HEADER_SIZE: usize = 4;
+
+/// Keys, cipher and delegated blob of a handshake completed by one of the drivers.
+#[derive(Clone)]
+pub struct HandshakeOutcome {
+    delegation_key: [u8; 32],
+    integrity_key: [u8; 32],
+    exporter_secret: [u8; 32],
+    cipher: Cipher,
+    key_exchange: KeyExchange,
+    dh_group: Option<DhGroup>,
+    version: ProtocolVersion,
+    blob: Option<SrdBlob>,
+    delegation_result: Option<DelegationResult>,
+}
+
+impl HandshakeOutcome {
+    fn new(srd: &Srd) -> HandshakeOutcome {
+        let (delegation_key, integrity_key) = srd._get_keys();
+        HandshakeOutcome {
+            delegation_key,
+            integrity_key,
+            exporter_secret: srd.exporter_secret(),
+            cipher: srd.get_cipher(),
+            key_exchange: srd.get_key_exchange(),
+            dh_group: srd.get_dh_group(),
+            version: srd.get_version(),
+            blob: srd.get_raw_blob(),
+            delegation_result: srd.get_delegation_result().cloned(),
+        }
+    }
+
+    pub fn get_keys(&self) -> ([u8; 32], [u8; 32]) {
+        (self.delegation_key, self.integrity_key)
+    }
+
+    pub fn get_delegation_key(&self) -> Vec<u8> {
+        self.delegation_key.to_vec()
+    }
+
+    pub fn get_integrity_key(&self) -> Vec<u8> {
+        self.integrity_key.to_vec()
+    }
+
+    /// Derive keying material for an upper-layer protocol, see `Srd::export_keying_material`.
+    pub fn export_keying_material(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>> {
+        export_keying_material(&self.exporter_secret, label, context, length)
+    }
+
+    pub fn get_cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    pub fn get_key_exchange(&self) -> KeyExchange {
+        self.key_exchange
+    }
+
+    pub fn get_dh_group(&self) -> Option<DhGroup> {
+        self.dh_group
+    }
+
+    pub fn get_version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    pub fn get_blob<T: Blob>(&self) -> Result<Option<T>> {
+        match self.blob {
+            Some(ref blob) => blob.get(),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_raw_blob(&self) -> Option<&SrdBlob> {
+        self.blob.as_ref()
+    }
+
+    /// Whether the server accepted the delegated blob, for handshakes with a delegation result.
+    pub fn get_delegation_result(&self) -> Option<&DelegationResult> {
+        self.delegation_result.as_ref()
+    }
+}
+
+impl fmt::Debug for HandshakeOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HandshakeOutcome")
+            .field("delegation_key", &"<redacted>")
+            .field("integrity_key", &"<redacted>")
+            .field("exporter_secret", &"<redacted>")
+            .field("cipher", &self.cipher)
+            .field("key_exchange", &self.key_exchange)
+            .field("dh_group", &self.dh_group)
+            .field("version", &self.version)
+            .field("blob_type", &self.blob.as_ref().map(|blob| blob.blob_type()))
+            .field("delegation_result", &self.delegation_result)
+            .finish()
+    }
+}
+
+impl Drop for HandshakeOutcome {
+    fn drop(&mut self) {
+        self.delegation_key.zeroize();
+        self.integrity_key.zeroize();
+        self.exporter_secret.zeroize();
+    }
+}
+
+fn frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0u8; FRAME_HEADER_SIZE];
+    LittleEndian::write_u32(&mut frame, message.len() as u32);
+    frame.extend_from_slice(message);
+    frame
+}
+
+fn frame_length(header: &[u8], max_message_size: usize) -> Result<usize> {
+    let length = LittleEndian::read_u32(header) as usize;
+    if length == 0 || length > max_message_size {
+        return Err(SrdError::InvalidDataLength);
+    }
+    Ok(length)
+}
+
+fn timed_out() -> SrdError {
+    SrdError::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, "SRD handshake timed out"))
+}
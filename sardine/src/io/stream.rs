@@ -0,0 +1,275 @@
+use std;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::{sleep, Sleep};
+
+use blobs::{Blob, SrdBlob};
+use credentials::{BlobProvider, CredentialValidator};
+#[cfg(not(feature = "wasm"))]
+use ephemeral_pool::EphemeralKeyPool;
+use io::{frame, frame_length, timed_out, HandshakeOutcome, FRAME_HEADER_SIZE};
+use srd::Srd;
+use srd_config::SrdConfig;
+use srd_errors::SrdError;
+use srd_state::{SrdState, SrdStep};
+use Result;
+
+enum Phase {
+    Listen,
+    Step(Vec<u8>),
+    Write { complete: bool },
+    Flush { complete: bool },
+    Read,
+    Done,
+}
+
+/// Future running a complete handshake over a tokio stream.
+///
+/// It resolves to the stream, ready to be used by the application, and the outcome of the handshake. Timeouts
+/// from the configuration are enforced, a handshake taking too long fails with an `ErrorKind::TimedOut` error.
+pub struct SrdStream<S> {
+    io: Option<S>,
+    srd: Option<Srd>,
+    config: Arc<SrdConfig>,
+    phase: Phase,
+    // Error reported once the alert telling the peer about it has been sent
+    failure: Option<SrdError>,
+
+    write_buffer: Vec<u8>,
+    written: usize,
+    read_buffer: Vec<u8>,
+    read: usize,
+
+    handshake_deadline: Option<Pin<Box<Sleep>>>,
+    io_deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> SrdStream<S> {
+    pub fn client(io: S, config: Arc<SrdConfig>) -> SrdStream<S> {
+        let srd = Srd::with_config(false, config.clone());
+        SrdStream::new(io, srd, config, Phase::Step(Vec::new()))
+    }
+
+    pub fn server(io: S, config: Arc<SrdConfig>) -> SrdStream<S> {
+        let srd = Srd::with_config(true, config.clone());
+        SrdStream::new(io, srd, config, Phase::Listen)
+    }
+
+    fn new(io: S, srd: Srd, config: Arc<SrdConfig>, phase: Phase) -> SrdStream<S> {
+        SrdStream {
+            io: Some(io),
+            srd: Some(srd),
+            config,
+            phase,
+            failure: None,
+            write_buffer: Vec::new(),
+            written: 0,
+            read_buffer: Vec::new(),
+            read: 0,
+            handshake_deadline: None,
+            io_deadline: None,
+        }
+    }
+
+    /// Set the blob a client delegates to the server.
+    pub fn with_blob<T: Blob>(mut self, blob: T) -> Result<SrdStream<S>> {
+        self.srd_mut()?.set_blob(blob)?;
+        Ok(self)
+    }
+
+    pub fn with_raw_blob(mut self, blob: SrdBlob) -> Result<SrdStream<S>> {
+        self.srd_mut()?.set_raw_blob(blob);
+        Ok(self)
+    }
+
+    /// Build the blob a client delegates only once the server is authenticated.
+    pub fn with_blob_provider(mut self, provider: Arc<dyn BlobProvider>) -> Result<SrdStream<S>> {
+        self.srd_mut()?.set_blob_provider(provider);
+        Ok(self)
+    }
+
+    /// Check the blob delegated to a server before the handshake completes.
+    pub fn with_credential_validator(mut self, validator: Arc<dyn CredentialValidator>) -> Result<SrdStream<S>> {
+        self.srd_mut()?.set_credential_validator(validator);
+        Ok(self)
+    }
+
+    /// Take the server key pairs from a pool filled in the background.
+    #[cfg(not(feature = "wasm"))]
+    pub fn with_key_pool(mut self, key_pool: Arc<EphemeralKeyPool>) -> Result<SrdStream<S>> {
+        self.srd_mut()?.set_key_pool(key_pool);
+        Ok(self)
+    }
+
+    fn srd_mut(&mut self) -> Result<&mut Srd> {
+        self.srd
+            .as_mut()
+            .ok_or_else(|| SrdError::Internal("SrdStream polled after completion".to_owned()))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SrdStream<S> {
+    fn poll_handshake(&mut self, cx: &mut Context) -> Poll<Result<()>> {
+        loop {
+            poll_deadline(&mut self.handshake_deadline, cx)?;
+            poll_deadline(&mut self.io_deadline, cx)?;
+
+            match mem::replace(&mut self.phase, Phase::Done) {
+                Phase::Listen => {
+                    // Timers can only be armed from within the runtime, so the first read is set up here
+                    self.phase = self.start_read();
+                }
+                Phase::Step(input) => {
+                    let srd = self.srd_mut()?;
+                    self.phase = match srd.step(&input) {
+                        Ok(SrdStep::Send(output)) => self.start_write(&output, false),
+                        Ok(SrdStep::SendAndComplete(output)) => self.start_write(&output, true),
+                        Ok(SrdStep::Complete { .. }) => Phase::Done,
+                        // Nothing would be left to send the result with once the future has resolved, servers
+                        // sending one need a credential validator
+                        Ok(SrdStep::Continue) if srd.state() == SrdState::AwaitingDelegationResult => {
+                            return Poll::Ready(Err(SrdError::Internal(
+                                "SrdStream servers need a credential validator to send a delegation result".to_owned(),
+                            )));
+                        }
+                        Ok(SrdStep::Continue) => self.start_read(),
+                        Err(error) => match srd.get_alert().map(|alert| alert.to_vec()) {
+                            Some(alert) => {
+                                self.failure = Some(error);
+                                self.start_write(&alert, true)
+                            }
+                            None => return Poll::Ready(Err(error)),
+                        },
+                    };
+                }
+                Phase::Write { complete } => {
+                    self.phase = Phase::Write { complete };
+                    while self.written < self.write_buffer.len() {
+                        let io = Pin::new(self.io.as_mut().expect("stream is only taken once done"));
+                        match io.poll_write(cx, &self.write_buffer[self.written..]) {
+                            Poll::Ready(Ok(0)) => return Poll::Ready(Err(write_zero())),
+                            Poll::Ready(Ok(count)) => self.written += count,
+                            Poll::Ready(Err(error)) => return Poll::Ready(Err(error.into())),
+                            Poll::Pending => return Poll::Pending,
+                        }
+                    }
+                    self.phase = Phase::Flush { complete };
+                }
+                Phase::Flush { complete } => {
+                    self.phase = Phase::Flush { complete };
+                    let io = Pin::new(self.io.as_mut().expect("stream is only taken once done"));
+                    match io.poll_flush(cx) {
+                        Poll::Ready(Ok(())) => {}
+                        Poll::Ready(Err(error)) => return Poll::Ready(Err(error.into())),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                    self.io_deadline = None;
+                    self.phase = if complete { Phase::Done } else { self.start_read() };
+                }
+                Phase::Read => {
+                    self.phase = Phase::Read;
+                    match self.poll_read_frame(cx)? {
+                        Poll::Ready(input) => {
+                            self.io_deadline = None;
+                            self.phase = Phase::Step(input);
+                        }
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                Phase::Done => return Poll::Ready(self.failure.take().map_or(Ok(()), Err)),
+            }
+        }
+    }
+
+    fn poll_read_frame(&mut self, cx: &mut Context) -> Result<Poll<Vec<u8>>> {
+        loop {
+            if self.read == self.read_buffer.len() {
+                if self.read_buffer.len() == FRAME_HEADER_SIZE {
+                    // The header is complete, now read the message itself
+                    let length = frame_length(&self.read_buffer, self.config.max_message_size())?;
+                    self.read_buffer = vec![0u8; length];
+                    self.read = 0;
+                } else {
+                    let message = mem::take(&mut self.read_buffer);
+                    return Ok(Poll::Ready(message));
+                }
+            }
+
+            let io = Pin::new(self.io.as_mut().expect("stream is only taken once done"));
+            let mut buffer = ReadBuf::new(&mut self.read_buffer[self.read..]);
+            match io.poll_read(cx, &mut buffer) {
+                Poll::Ready(Ok(())) => {
+                    let count = buffer.filled().len();
+                    if count == 0 {
+                        return Err(SrdError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    self.read += count;
+                }
+                Poll::Ready(Err(error)) => return Err(error.into()),
+                Poll::Pending => return Ok(Poll::Pending),
+            }
+        }
+    }
+
+    fn start_write(&mut self, message: &[u8], complete: bool) -> Phase {
+        self.write_buffer = frame(message);
+        self.written = 0;
+        self.io_deadline = self.config.write_timeout().map(deadline);
+        Phase::Write { complete }
+    }
+
+    fn start_read(&mut self) -> Phase {
+        self.read_buffer = vec![0u8; FRAME_HEADER_SIZE];
+        self.read = 0;
+        self.io_deadline = self.config.read_timeout().map(deadline);
+        Phase::Read
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Future for SrdStream<S> {
+    type Output = Result<(S, HandshakeOutcome)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.handshake_deadline.is_none() {
+            if let Some(timeout) = this.config.handshake_timeout() {
+                this.handshake_deadline = Some(deadline(timeout));
+            }
+        }
+
+        match this.poll_handshake(cx) {
+            Poll::Ready(Ok(())) => {
+                let io = this.io.take().expect("stream is only taken once done");
+                let srd = this.srd.take().expect("handshake is only taken once done");
+                Poll::Ready(Ok((io, HandshakeOutcome::new(&srd))))
+            }
+            // Failing to send the alert doesn't hide the error that caused it
+            Poll::Ready(Err(error)) => Poll::Ready(Err(this.failure.take().unwrap_or(error))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+fn deadline(timeout: Duration) -> Pin<Box<Sleep>> {
+    Box::pin(sleep(timeout))
+}
+
+fn poll_deadline(deadline: &mut Option<Pin<Box<Sleep>>>, cx: &mut Context) -> Result<()> {
+    if let Some(ref mut sleep) = *deadline {
+        if sleep.as_mut().poll(cx).is_ready() {
+            return Err(timed_out());
+        }
+    }
+    Ok(())
+}
+
+fn write_zero() -> SrdError {
+    SrdError::Io(std::io::ErrorKind::WriteZero.into())
+}
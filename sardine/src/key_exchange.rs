@@ -0,0 +1,111 @@
+use rand::rngs::OsRng;
+use rand::RngCore;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use dh_group::DhGroup;
+use modexp::generator_table;
+use srd_errors::SrdError;
+use Result;
+
+const FINITE_FIELD_FLAG: u16 = 0x0001;
+const X25519_FLAG: u16 = 0x0002;
+
+pub const X25519_KEY_SIZE: u16 = 32;
+
+/// Key agreement used to compute the shared secret of a handshake.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
+pub enum KeyExchange {
+    /// Finite-field Diffie-Hellman, with a prime of the negotiated key size
+    FiniteField,
+    X25519,
+}
+
+impl KeyExchange {
+    pub fn flag(self) -> u16 {
+        match self {
+            KeyExchange::FiniteField => FINITE_FIELD_FLAG,
+            KeyExchange::X25519 => X25519_FLAG,
+        }
+    }
+
+    /// Key exchanges advertised by a client. Clients predating the negotiation don't set any flag and only support
+    /// finite-field Diffie-Hellman with the legacy groups.
+    pub fn from_flags(flags: u16) -> Vec<Self> {
+        if flags == 0 {
+            return vec![KeyExchange::FiniteField];
+        }
+
+        let mut key_exchanges = Vec::new();
+        if flags & FINITE_FIELD_FLAG != 0 {
+            key_exchanges.push(KeyExchange::FiniteField)
+        };
+        if flags & X25519_FLAG != 0 {
+            key_exchanges.push(KeyExchange::X25519)
+        };
+        key_exchanges
+    }
+}
+
+/// Generate a finite-field private exponent for a group and return it with the matching public key.
+pub fn dh_key_pair(group: DhGroup) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut private_key = vec![0u8; group.exponent_size()];
+    OsRng.try_fill_bytes(&mut private_key)?;
+    let public_key = generator_table(group)?.pow(&private_key)?;
+    Ok((private_key, public_key))
+}
+
+/// Generate an X25519 private key and return it with the matching public key.
+pub fn x25519_key_pair() -> Result<([u8; 32], [u8; 32])> {
+    let mut private_key = [0u8; 32];
+    OsRng.try_fill_bytes(&mut private_key)?;
+    let public_key = PublicKey::from(&StaticSecret::from(private_key));
+    Ok((private_key, public_key.to_bytes()))
+}
+
+pub fn x25519_shared_secret(private_key: &[u8; 32], peer_public_key: &[u8]) -> Result<Vec<u8>> {
+    if peer_public_key.len() != X25519_KEY_SIZE as usize {
+        return Err(SrdError::InvalidKeySize);
+    }
+
+    let mut public_key = [0u8; 32];
+    public_key.copy_from_slice(peer_public_key);
+
+    let shared_secret = StaticSecret::from(*private_key).diffie_hellman(&PublicKey::from(public_key));
+
+    // A low order point from the peer would make the secret predictable
+    if !shared_secret.was_contributory() {
+        return Err(SrdError::PublicKeyNotInSubgroup);
+    }
+
+    Ok(shared_secret.as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod test {
+    use key_exchange::{x25519_key_pair, x25519_shared_secret, KeyExchange};
+
+    #[test]
+    fn key_exchange_flags() {
+        assert_eq!(KeyExchange::from_flags(0), [KeyExchange::FiniteField]);
+        assert_eq!(
+            KeyExchange::from_flags(KeyExchange::X25519.flag() | KeyExchange::FiniteField.flag()),
+            [KeyExchange::FiniteField, KeyExchange::X25519]
+        );
+    }
+
+    #[test]
+    fn x25519_agreement() {
+        let (client_private, client_public) = x25519_key_pair().unwrap();
+        let (server_private, server_public) = x25519_key_pair().unwrap();
+
+        assert_eq!(
+            x25519_shared_secret(&client_private, &server_public).unwrap(),
+            x25519_shared_secret(&server_private, &client_public).unwrap()
+        );
+
+        // The identity point can't be used to force the shared secret
+        assert!(x25519_shared_secret(&client_private, &[0u8; 32]).is_err());
+        assert!(x25519_shared_secret(&client_private, &[9u8; 31]).is_err());
+    }
+}
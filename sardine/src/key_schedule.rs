@@ -0,0 +1,132 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use srd_errors::SrdError;
+use Result;
+
+const HASH_SIZE: usize = 32;
+
+/// HKDF-SHA256 (RFC 5869), keeping the pseudorandom key extracted from the input keying material.
+pub struct Hkdf {
+    prk: [u8; HASH_SIZE],
+}
+
+impl Hkdf {
+    pub fn extract(salt: &[u8], ikm: &[u8]) -> Hkdf {
+        let mut prk = [0u8; HASH_SIZE];
+        prk.copy_from_slice(&hmac(salt, &[ikm]));
+        Hkdf { prk }
+    }
+
+    /// Start from a secret that is already uniformly random.
+    pub fn from_prk(prk: &[u8; HASH_SIZE]) -> Hkdf {
+        Hkdf { prk: *prk }
+    }
+
+    /// Fill `output`, of at most 255 times the hash size, with keying material bound to `info`.
+    pub fn expand(&self, info: &[&[u8]], output: &mut [u8]) {
+        assert!(output.len() <= 255 * HASH_SIZE, "HKDF output too long");
+
+        let mut block: Vec<u8> = Vec::new();
+        for (counter, chunk) in output.chunks_mut(HASH_SIZE).enumerate() {
+            let mut input = vec![block.as_slice()];
+            input.extend_from_slice(info);
+            let counter = [counter as u8 + 1];
+            input.push(&counter);
+
+            let next = hmac(&self.prk, &input);
+            block.zeroize();
+            block = next;
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        block.zeroize();
+    }
+}
+
+impl Drop for Hkdf {
+    fn drop(&mut self) {
+        self.prk.zeroize();
+    }
+}
+
+/// Expand the exporter secret of a handshake for a label and a context. The label, the context and the length are
+/// all bound to the output, so that none of them can be changed to get the bytes exported for another one.
+pub fn export_keying_material(
+    exporter_secret: &[u8; HASH_SIZE],
+    label: &[u8],
+    context: &[u8],
+    length: usize,
+) -> Result<Vec<u8>> {
+    if length == 0 || length > 255 * HASH_SIZE {
+        return Err(SrdError::InvalidDataLength);
+    }
+
+    let label_length = (label.len() as u32).to_le_bytes();
+    let context_hash = Sha256::digest(context);
+    let length_bytes = (length as u16).to_le_bytes();
+
+    let mut output = vec![0u8; length];
+    Hkdf::from_prk(exporter_secret).expand(
+        &[b"srd exporter", &label_length, label, &context_hash, &length_bytes],
+        &mut output,
+    );
+    Ok(output)
+}
+
+fn hmac(key: &[u8], input: &[&[u8]]) -> Vec<u8> {
+    let mut hmac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts keys of any size");
+    for data in input {
+        hmac.input(data);
+    }
+    hmac.result().code().to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use key_schedule::{export_keying_material, Hkdf};
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn hkdf_rfc5869() {
+        // Test case 1
+        let hkdf = Hkdf::extract(&from_hex("000102030405060708090a0b0c"), &[0x0b; 22]);
+        assert_eq!(hkdf.prk.to_vec(), from_hex("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"));
+
+        let mut okm = [0u8; 42];
+        hkdf.expand(&[&from_hex("f0f1f2f3f4"), &from_hex("f5f6f7f8f9")], &mut okm);
+        assert_eq!(
+            okm.to_vec(),
+            from_hex("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865")
+        );
+
+        // Test case 3, without salt nor info
+        let hkdf = Hkdf::extract(&[], &[0x0b; 22]);
+        let mut okm = [0u8; 42];
+        hkdf.expand(&[], &mut okm);
+        assert_eq!(
+            okm.to_vec(),
+            from_hex("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8")
+        );
+    }
+
+    #[test]
+    fn exported_keying_material() {
+        let secret = [3u8; 32];
+        let exported = export_keying_material(&secret, b"label", b"context", 40).unwrap();
+        assert_eq!(exported.len(), 40);
+        assert_eq!(export_keying_material(&secret, b"label", b"context", 40).unwrap(), exported);
+
+        // Every input changes the output
+        assert_ne!(export_keying_material(&secret, b"other", b"context", 40).unwrap(), exported);
+        assert_ne!(export_keying_material(&secret, b"label", b"other", 40).unwrap(), exported);
+        assert_ne!(export_keying_material(&secret, b"labelc", b"ontext", 40).unwrap(), exported);
+        assert_ne!(export_keying_material(&secret, b"label", b"context", 32).unwrap()[..], exported[..32]);
+
+        assert!(export_keying_material(&secret, b"label", b"context", 0).is_err());
+        assert!(export_keying_material(&secret, b"label", b"context", 255 * 32 + 1).is_err());
+    }
+}
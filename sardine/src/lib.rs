@@ -1,10 +1,16 @@
 extern crate byteorder;
+extern crate crypto_bigint;
 extern crate hmac;
 extern crate num_bigint;
+extern crate num_integer;
+extern crate num_traits;
 extern crate rand;
 extern crate sha2;
+extern crate x25519_dalek;
+extern crate zeroize;
 
 extern crate chacha;
+extern crate chacha20poly1305;
 
 #[macro_use]
 extern crate cfg_if;
@@ -16,19 +22,58 @@ cfg_if! {
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "tokio")] {
+        extern crate tokio;
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "htpasswd")] {
+        extern crate argon2;
+        extern crate bcrypt;
+    }
+}
+
 mod cipher;
 
 pub mod blobs;
+pub mod credentials;
+mod dh_group;
 mod dh_params;
+mod dh_validation;
+pub mod io;
+mod key_exchange;
+mod key_schedule;
 mod messages;
+mod modexp;
+mod protocol_version;
+mod session;
 pub mod srd;
+mod srd_client;
+mod srd_config;
+mod srd_decoder;
 mod srd_errors;
+mod srd_server;
+mod srd_state;
 
 pub type Result<T> = std::result::Result<T, srd_errors::SrdError>;
 
 pub use cipher::Cipher;
+pub use dh_group::DhGroup;
+pub use key_exchange::KeyExchange;
+pub use messages::{AlertCode, DelegationResult, Message, SrdMessage, SrdMessageType};
+pub use protocol_version::ProtocolVersion;
+pub use session::SrdSession;
 pub use srd::Srd;
-pub use srd_errors::SrdError;
+pub use srd_client::{ClientAccepted, ClientDelegated, ClientDelegation, ClientDone, ClientInitiated, SrdClient};
+pub use srd_config::{SrdConfig, SrdConfigBuilder};
+pub use srd_decoder::SrdDecoder;
+pub use srd_errors::{SrdError, SrdErrorKind};
+pub use srd_server::{
+    ServerConfirmation, ServerConfirmed, ServerDelegated, ServerDelegation, ServerDone, ServerOffered, SrdServer,
+};
+pub use srd_state::{SrdState, SrdStep};
 
 cfg_if! {
     if #[cfg(feature = "wasm")] {
@@ -36,13 +81,17 @@ cfg_if! {
         pub use srd::SrdJsResult;
     }
     else {
+        mod ephemeral_pool;
         pub mod ffi;
+
+        pub use ephemeral_pool::EphemeralKeyPool;
     }
 }
 
 cfg_if! {
     if #[cfg(feature = "aes")] {
         extern crate aes256 as aes;
+        extern crate aes_gcm;
         extern crate block_modes;
 
     }
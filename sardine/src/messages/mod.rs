@@ -1,12 +1,16 @@
 mod srd_accept;
+mod srd_alert;
 mod srd_confirm;
 mod srd_delegate;
 mod srd_header;
 mod srd_initiate;
 mod srd_message;
 mod srd_offer;
+mod srd_rekey;
+mod srd_result;
 
 pub const SRD_SIGNATURE: u32 = 0x00445253;
+pub const SRD_HEADER_SIZE: usize = 8;
 
 pub mod srd_msg_id {
     pub const SRD_INITIATE_MSG_ID: u8 = 1;
@@ -14,28 +18,85 @@ pub mod srd_msg_id {
     pub const SRD_ACCEPT_MSG_ID: u8 = 3;
     pub const SRD_CONFIRM_MSG_ID: u8 = 4;
     pub const SRD_DELEGATE_MSG_ID: u8 = 5;
+    pub const SRD_REKEY_MSG_ID: u8 = 6;
+    pub const SRD_ALERT_MSG_ID: u8 = 7;
+    pub const SRD_RESULT_MSG_ID: u8 = 8;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
+pub enum SrdMessageType {
+    Initiate,
+    Offer,
+    Accept,
+    Confirm,
+    Delegate,
+    Rekey,
+    Alert,
+    Result,
+}
+
+impl SrdMessageType {
+    pub fn from_id(msg_type: u8) -> Option<Self> {
+        match msg_type {
+            srd_msg_id::SRD_INITIATE_MSG_ID => Some(SrdMessageType::Initiate),
+            srd_msg_id::SRD_OFFER_MSG_ID => Some(SrdMessageType::Offer),
+            srd_msg_id::SRD_ACCEPT_MSG_ID => Some(SrdMessageType::Accept),
+            srd_msg_id::SRD_CONFIRM_MSG_ID => Some(SrdMessageType::Confirm),
+            srd_msg_id::SRD_DELEGATE_MSG_ID => Some(SrdMessageType::Delegate),
+            srd_msg_id::SRD_REKEY_MSG_ID => Some(SrdMessageType::Rekey),
+            srd_msg_id::SRD_ALERT_MSG_ID => Some(SrdMessageType::Alert),
+            srd_msg_id::SRD_RESULT_MSG_ID => Some(SrdMessageType::Result),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        match self {
+            SrdMessageType::Initiate => srd_msg_id::SRD_INITIATE_MSG_ID,
+            SrdMessageType::Offer => srd_msg_id::SRD_OFFER_MSG_ID,
+            SrdMessageType::Accept => srd_msg_id::SRD_ACCEPT_MSG_ID,
+            SrdMessageType::Confirm => srd_msg_id::SRD_CONFIRM_MSG_ID,
+            SrdMessageType::Delegate => srd_msg_id::SRD_DELEGATE_MSG_ID,
+            SrdMessageType::Rekey => srd_msg_id::SRD_REKEY_MSG_ID,
+            SrdMessageType::Alert => srd_msg_id::SRD_ALERT_MSG_ID,
+            SrdMessageType::Result => srd_msg_id::SRD_RESULT_MSG_ID,
+        }
+    }
 }
 
 pub mod srd_flags {
     pub const SRD_FLAG_MAC: u16 = 0x0001;
     pub const SRD_FLAG_CBT: u16 = 0x0002;
     pub const SRD_FLAG_SKIP: u16 = 0x0004;
+    pub const SRD_FLAG_X25519: u16 = 0x0008;
+    pub const SRD_FLAG_DH_GROUP: u16 = 0x0010;
+    pub const SRD_FLAG_VERSION: u16 = 0x0020;
+    pub const SRD_FLAG_RESULT: u16 = 0x0040;
+    pub const SRD_FLAG_BLOB_TYPES: u16 = 0x0080;
 }
 
 pub use messages::srd_accept::SrdAccept;
+pub use messages::srd_alert::{AlertCode, SrdAlert};
 pub use messages::srd_confirm::SrdConfirm;
 pub use messages::srd_delegate::SrdDelegate;
 pub use messages::srd_header::SrdHeader;
 pub use messages::srd_initiate::SrdInitiate;
+pub use messages::srd_message::{message_size, MessageSize};
 pub use messages::srd_message::Message;
 pub use messages::srd_message::SrdMessage;
 pub use messages::srd_offer::SrdOffer;
+pub use messages::srd_rekey::SrdRekey;
+pub use messages::srd_result::{DelegationResult, SrdResult};
 
 pub use messages::srd_accept::new_srd_accept_msg;
+pub use messages::srd_alert::new_srd_alert_msg;
 pub use messages::srd_confirm::new_srd_confirm_msg;
 pub use messages::srd_delegate::new_srd_delegate_msg;
 pub use messages::srd_initiate::new_srd_initiate_msg;
-pub use messages::srd_offer::new_srd_offer_msg;
+pub use messages::srd_offer::{new_srd_dh_group_offer_msg, new_srd_offer_msg, new_srd_x25519_offer_msg};
+pub use messages::srd_rekey::new_srd_rekey_msg;
+pub use messages::srd_result::new_srd_result_msg;
 
 fn expand_start<T: Default>(buffer: &mut Vec<T>, new_size: usize) {
     if new_size > buffer.len() {
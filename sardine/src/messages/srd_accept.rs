@@ -18,6 +18,10 @@ pub struct SrdAccept {
 }
 
 impl SrdAccept {
+    pub fn key_size(&self) -> u16 {
+        self.key_size
+    }
+
     pub fn mac(&self) -> &[u8] {
         &self.mac
     }
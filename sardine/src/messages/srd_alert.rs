@@ -0,0 +1,211 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::Read;
+use std::io::Write;
+
+use messages::{srd_msg_id, Message, SrdHeader, SrdMessage};
+use srd_errors::SrdError;
+use Result;
+
+/// Reason of a failed handshake, as sent to the peer in an alert message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertCode {
+    Unspecified,
+    BadSequence,
+    Protocol,
+    Cipher,
+    KeyExchange,
+    InvalidKeySize,
+    InvalidPublicKey,
+    InvalidMac,
+    InvalidCbt,
+    InvalidCert,
+    InvalidCredentials,
+    UnsupportedVersion,
+    InvalidBlob,
+}
+
+impl AlertCode {
+    /// Code reporting a local error to the peer. Errors that don't come from the exchange are unspecified.
+    pub fn from_error(error: &SrdError) -> AlertCode {
+        match *error {
+            SrdError::BadSequence => AlertCode::BadSequence,
+            SrdError::InvalidDataLength
+            | SrdError::InvalidSignature
+            | SrdError::UnknownMsgType
+            | SrdError::Proto(_) => AlertCode::Protocol,
+            SrdError::Cipher => AlertCode::Cipher,
+            SrdError::KeyExchange | SrdError::UntrustedDhGroup | SrdError::WeakDhGenerator => AlertCode::KeyExchange,
+            SrdError::InvalidKeySize => AlertCode::InvalidKeySize,
+            SrdError::PublicKeyOutOfRange | SrdError::PublicKeyNotInSubgroup => AlertCode::InvalidPublicKey,
+            SrdError::InvalidMac => AlertCode::InvalidMac,
+            SrdError::InvalidCbt => AlertCode::InvalidCbt,
+            SrdError::InvalidCert => AlertCode::InvalidCert,
+            SrdError::InvalidCredentials => AlertCode::InvalidCredentials,
+            SrdError::UnsupportedVersion => AlertCode::UnsupportedVersion,
+            SrdError::MissingBlob | SrdError::BlobFormatError | SrdError::UnsupportedBlobType(_) => {
+                AlertCode::InvalidBlob
+            }
+            SrdError::PeerAlert(code) => code,
+            _ => AlertCode::Unspecified,
+        }
+    }
+
+    /// Unknown codes, from a more recent peer, are unspecified.
+    pub fn from_code(code: u16) -> AlertCode {
+        match code {
+            1 => AlertCode::BadSequence,
+            2 => AlertCode::Protocol,
+            3 => AlertCode::Cipher,
+            4 => AlertCode::KeyExchange,
+            5 => AlertCode::InvalidKeySize,
+            6 => AlertCode::InvalidPublicKey,
+            7 => AlertCode::InvalidMac,
+            8 => AlertCode::InvalidCbt,
+            9 => AlertCode::InvalidCert,
+            10 => AlertCode::InvalidCredentials,
+            11 => AlertCode::UnsupportedVersion,
+            12 => AlertCode::InvalidBlob,
+            _ => AlertCode::Unspecified,
+        }
+    }
+
+    pub fn code(self) -> u16 {
+        match self {
+            AlertCode::Unspecified => 0,
+            AlertCode::BadSequence => 1,
+            AlertCode::Protocol => 2,
+            AlertCode::Cipher => 3,
+            AlertCode::KeyExchange => 4,
+            AlertCode::InvalidKeySize => 5,
+            AlertCode::InvalidPublicKey => 6,
+            AlertCode::InvalidMac => 7,
+            AlertCode::InvalidCbt => 8,
+            AlertCode::InvalidCert => 9,
+            AlertCode::InvalidCredentials => 10,
+            AlertCode::UnsupportedVersion => 11,
+            AlertCode::InvalidBlob => 12,
+        }
+    }
+
+    /// Fixed text sent along the code, which doesn't reveal anything about the local error.
+    pub fn text(self) -> &'static str {
+        match self {
+            AlertCode::Unspecified => "Handshake error",
+            AlertCode::BadSequence => "Sequence error",
+            AlertCode::Protocol => "Protocol error",
+            AlertCode::Cipher => "Cipher error",
+            AlertCode::KeyExchange => "Key exchange error",
+            AlertCode::InvalidKeySize => "Key Size error",
+            AlertCode::InvalidPublicKey => "Public key error",
+            AlertCode::InvalidMac => "MAC error",
+            AlertCode::InvalidCbt => "CBT error",
+            AlertCode::InvalidCert => "Certificate error",
+            AlertCode::InvalidCredentials => "Credentials error",
+            AlertCode::UnsupportedVersion => "Protocol version error",
+            AlertCode::InvalidBlob => "Blob error",
+        }
+    }
+}
+
+/// Last message of a failed handshake, telling the peer why it failed. It isn't authenticated: the peer may not
+/// have the keys yet, or may be the reason they don't match.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SrdAlert {
+    code: u16,
+    text: Option<String>,
+}
+
+impl SrdAlert {
+    pub fn code(&self) -> AlertCode {
+        AlertCode::from_code(self.code)
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+}
+
+impl Message for SrdAlert {
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self>
+        where
+            Self: Sized,
+    {
+        let code = reader.read_u16::<LittleEndian>()?;
+        let text_size = reader.read_u16::<LittleEndian>()?;
+
+        let text = if text_size > 0 {
+            let mut text = vec![0u8; text_size as usize];
+            reader.read_exact(&mut text)?;
+            Some(String::from_utf8(text)?)
+        } else {
+            None
+        };
+
+        Ok(SrdAlert { code, text })
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let text = self.text.as_ref().map(|text| text.as_bytes()).unwrap_or_default();
+        if text.len() > u16::MAX as usize {
+            return Err(SrdError::InvalidDataLength);
+        }
+
+        writer.write_u16::<LittleEndian>(self.code)?;
+        writer.write_u16::<LittleEndian>(text.len() as u16)?;
+        writer.write_all(text)?;
+        Ok(())
+    }
+}
+
+pub fn new_srd_alert_msg(seq_num: u8, code: AlertCode, text: Option<String>) -> SrdMessage {
+    let hdr = SrdHeader::new(srd_msg_id::SRD_ALERT_MSG_ID, seq_num, false, false);
+    let alert = SrdAlert {
+        code: code.code(),
+        text,
+    };
+
+    SrdMessage::Alert(hdr, alert)
+}
+
+#[cfg(test)]
+mod test {
+    use messages::{message_size, new_srd_alert_msg, AlertCode, Message, MessageSize, SrdMessage};
+    use std;
+
+    #[test]
+    fn alert_encoding() {
+        let msg = new_srd_alert_msg(3, AlertCode::InvalidMac, Some("MAC error".to_owned()));
+
+        let mut buffer: Vec<u8> = Vec::new();
+        msg.write_to(&mut buffer).unwrap();
+        // Header, code, text size and text
+        assert_eq!(buffer.len(), 8 + 4 + 9);
+        match message_size(&buffer).unwrap() {
+            MessageSize::Complete(size) => assert_eq!(size, buffer.len()),
+            MessageSize::Partial(_) => panic!("alert size should be known"),
+        }
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        let read = SrdMessage::read_from(&mut cursor).unwrap();
+        assert_eq!(read, msg);
+        match read {
+            SrdMessage::Alert(_, alert) => {
+                assert_eq!(alert.code(), AlertCode::InvalidMac);
+                assert_eq!(alert.text(), Some("MAC error"));
+            }
+            _ => panic!("not an alert"),
+        }
+
+        // Codes unknown to this version are read as unspecified
+        let mut buffer = Vec::new();
+        new_srd_alert_msg(0, AlertCode::Unspecified, None).write_to(&mut buffer).unwrap();
+        buffer[8] = 0xFF;
+        match SrdMessage::read_from(&mut std::io::Cursor::new(buffer.as_slice())).unwrap() {
+            SrdMessage::Alert(_, alert) => {
+                assert_eq!(alert.code(), AlertCode::Unspecified);
+                assert_eq!(alert.text(), None);
+            }
+            _ => panic!("not an alert"),
+        }
+    }
+}
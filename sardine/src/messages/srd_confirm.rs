@@ -1,14 +1,17 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::Read;
 use std::io::Write;
 
 use messages::{
     srd_message::ReadMac, srd_msg_id, Message, SrdHeader, SrdMessage,
 };
+use srd_errors::SrdError;
 use Result;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SrdConfirm {
     pub cbt: [u8; 32],
+    pub blob_types: Option<Vec<String>>,
     mac: [u8; 32],
 }
 
@@ -20,6 +23,39 @@ impl SrdConfirm {
     pub fn set_mac(&mut self, mac: &[u8]) {
         self.mac.clone_from_slice(mac);
     }
+
+    /// Read a confirm whose layout is given by the header flags.
+    ///
+    /// The blob types accepted by the server follow the CBT when the header has the blob types flag, as their size
+    /// (u16) and the null-terminated type names.
+    pub fn read_with_header<R: Read>(reader: &mut R, hdr: &SrdHeader) -> Result<Self> {
+        let mut cbt = [0u8; 32];
+        reader.read_exact(&mut cbt)?;
+
+        let mut blob_types = None;
+        if hdr.has_blob_types() {
+            let size = reader.read_u16::<LittleEndian>()?;
+            let mut names = vec![0u8; size as usize];
+            reader.read_exact(&mut names)?;
+            blob_types = Some(read_blob_types(&names)?);
+        }
+
+        let mut mac = [0u8; 32];
+        reader.read_mac(&mut mac)?;
+
+        Ok(SrdConfirm { cbt, blob_types, mac })
+    }
+}
+
+fn read_blob_types(names: &[u8]) -> Result<Vec<String>> {
+    match names.split_last() {
+        None => Ok(Vec::new()),
+        Some((0, names)) => Ok(names
+            .split(|c| *c == 0)
+            .map(|name| name.iter().map(|c| *c as char).collect())
+            .collect()),
+        Some(_) => Err(SrdError::Proto("Blob type names must be null-terminated".to_owned())),
+    }
 }
 
 impl Message for SrdConfirm {
@@ -33,11 +69,29 @@ impl Message for SrdConfirm {
         let mut mac = [0u8; 32];
         reader.read_mac(&mut mac)?;
 
-        Ok(SrdConfirm { cbt, mac })
+        Ok(SrdConfirm {
+            cbt,
+            blob_types: None,
+            mac,
+        })
     }
 
     fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
         writer.write_all(&self.cbt)?;
+        if let Some(ref blob_types) = self.blob_types {
+            // Names are covered by the MAC as written, they must be decoded the same way by the peer
+            let size: usize = blob_types.iter().map(|name| name.len() + 1).sum();
+            let is_blob_type = |name: &String| name.bytes().all(|c| c.is_ascii() && c != 0);
+            if size > u16::MAX as usize || !blob_types.iter().all(is_blob_type) {
+                return Err(SrdError::InvalidDataLength);
+            }
+
+            writer.write_u16::<LittleEndian>(size as u16)?;
+            for name in blob_types {
+                writer.write_all(name.as_bytes())?;
+                writer.write_u8(0u8)?;
+            }
+        }
         writer.write_all(&self.mac)?;
         Ok(())
     }
@@ -45,7 +99,11 @@ impl Message for SrdConfirm {
 
 pub fn new_srd_confirm_msg(seq_num: u8, use_cbt: bool, cbt: [u8; 32]) -> SrdMessage {
     let hdr = SrdHeader::new(srd_msg_id::SRD_CONFIRM_MSG_ID, seq_num, use_cbt, true);
-    let confirm = SrdConfirm { cbt, mac: [0u8; 32] };
+    let confirm = SrdConfirm {
+        cbt,
+        blob_types: None,
+        mac: [0u8; 32],
+    };
 
     SrdMessage::Confirm(hdr, confirm)
 }
@@ -53,6 +111,7 @@ pub fn new_srd_confirm_msg(seq_num: u8, use_cbt: bool, cbt: [u8; 32]) -> SrdMess
 #[cfg(test)]
 mod test {
     use messages::{new_srd_confirm_msg, srd_msg_id::SRD_CONFIRM_MSG_ID, Message, SrdMessage, SRD_SIGNATURE};
+    use srd_errors::SrdError;
     use std;
 
     #[test]
@@ -75,4 +134,38 @@ mod test {
             Err(_) => assert!(false),
         }
     }
+
+    #[test]
+    fn confirm_blob_types_encoding() {
+        let mut msg = new_srd_confirm_msg(3, true, [0u8; 32]);
+        msg.set_blob_types(&["Logon".to_owned(), "Basic".to_owned()]);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        msg.write_to(&mut buffer).unwrap();
+        // Header, CBT, blob types size, blob types and MAC
+        assert_eq!(buffer.len(), 8 + 32 + 2 + 12 + 32);
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        let read = SrdMessage::read_from(&mut cursor).unwrap();
+        assert_eq!(read, msg);
+        match read {
+            SrdMessage::Confirm(_, confirm) => assert_eq!(confirm.blob_types.unwrap(), ["Logon", "Basic"]),
+            _ => panic!("not a confirm"),
+        }
+    }
+
+    #[test]
+    fn confirm_invalid_blob_types() {
+        let invalid_blob_types = [vec!["Logon\u{e9}".to_owned()], vec!["Log\0on".to_owned()], vec!["a".repeat(70000)]];
+
+        for blob_types in invalid_blob_types.iter() {
+            let mut msg = new_srd_confirm_msg(3, true, [0u8; 32]);
+            msg.set_blob_types(blob_types);
+
+            match msg.write_to(&mut Vec::new()) {
+                Err(SrdError::InvalidDataLength) => {}
+                _ => panic!("invalid blob types written"),
+            }
+        }
+    }
 }
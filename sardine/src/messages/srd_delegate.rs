@@ -2,6 +2,7 @@ use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use cipher::Cipher;
 use std;
 use std::io::{Read, Write};
+use zeroize::Zeroizing;
 
 use blobs::SrdBlob;
 use messages::{srd_message::ReadMac, srd_msg_id, Message, SrdHeader, SrdMessage};
@@ -18,8 +19,10 @@ pub struct SrdDelegate {
 }
 
 impl SrdDelegate {
+    /// Decrypt the delegated blob. With an AEAD cipher, the blob is only parsed once its tag is verified.
     pub fn get_data(&self, cipher: Cipher, key: &[u8], iv: &[u8]) -> Result<SrdBlob> {
-        let buffer = cipher.decrypt_data(&self.encrypted_blob, key, iv)?;
+        // The decrypted blob holds credentials
+        let buffer = Zeroizing::new(cipher.decrypt_data(&self.encrypted_blob, key, iv)?);
 
         let mut cursor = std::io::Cursor::new(buffer.as_slice());
         let srd_blob = SrdBlob::read_from(&mut cursor)?;
@@ -75,8 +78,8 @@ pub fn new_srd_delegate_msg(
     delegation_key: &[u8],
     iv: &[u8],
 ) -> Result<SrdMessage> {
-    let mut v_blob = Vec::new();
-    srd_blob.write_to(&mut v_blob)?;
+    let mut v_blob = Zeroizing::new(Vec::new());
+    srd_blob.write_to(&mut *v_blob)?;
     let encrypted_blob = cipher.encrypt_data(&v_blob, delegation_key, iv)?;
 
     let hdr = SrdHeader::new(srd_msg_id::SRD_DELEGATE_MSG_ID, seq_num, use_cbt, true);
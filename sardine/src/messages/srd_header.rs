@@ -58,6 +58,46 @@ impl SrdHeader {
         self.flags |= SRD_FLAG_SKIP
     }
 
+    pub fn has_x25519(&self) -> bool {
+        self.flags & SRD_FLAG_X25519 != 0
+    }
+
+    pub fn add_x25519_flag(&mut self) {
+        self.flags |= SRD_FLAG_X25519
+    }
+
+    pub fn has_dh_group(&self) -> bool {
+        self.flags & SRD_FLAG_DH_GROUP != 0
+    }
+
+    pub fn add_dh_group_flag(&mut self) {
+        self.flags |= SRD_FLAG_DH_GROUP
+    }
+
+    pub fn has_version(&self) -> bool {
+        self.flags & SRD_FLAG_VERSION != 0
+    }
+
+    pub fn add_version_flag(&mut self) {
+        self.flags |= SRD_FLAG_VERSION
+    }
+
+    pub fn has_result(&self) -> bool {
+        self.flags & SRD_FLAG_RESULT != 0
+    }
+
+    pub fn add_result_flag(&mut self) {
+        self.flags |= SRD_FLAG_RESULT
+    }
+
+    pub fn has_blob_types(&self) -> bool {
+        self.flags & SRD_FLAG_BLOB_TYPES != 0
+    }
+
+    pub fn add_blob_types_flag(&mut self) {
+        self.flags |= SRD_FLAG_BLOB_TYPES
+    }
+
     pub fn validate_flags(&self, mac_expected: bool) -> Result<()> {
         if !self.has_mac() && mac_expected {
             return Err(SrdError::Proto(format!(
@@ -8,11 +8,12 @@ use SrdError;
 pub struct SrdInitiate {
     ciphers: u32,
     key_size: u16,
-    reserved: u16,
+    key_exchanges: u16,
+    pub version: Option<u16>,
 }
 
 impl SrdInitiate {
-    pub fn new(ciphers: u32, key_size: u16) -> Result<Self> {
+    pub fn new(ciphers: u32, key_size: u16, key_exchanges: u16) -> Result<Self> {
         match key_size {
             256 | 512 | 1024 => {}
             _ => return Err(SrdError::InvalidKeySize),
@@ -21,12 +22,29 @@ impl SrdInitiate {
         Ok(SrdInitiate {
             ciphers,
             key_size,
-            reserved: 0,
+            key_exchanges,
+            version: None,
         })
     }
+
     pub fn key_size(&self) -> u16 {
         self.key_size
     }
+
+    /// Key exchange flags, this field was reserved and is 0 for older clients.
+    pub fn key_exchanges(&self) -> u16 {
+        self.key_exchanges
+    }
+
+    /// Read an initiate message, followed by the highest protocol version of the client when the header has the
+    /// version flag.
+    pub fn read_with_header<R: Read>(reader: &mut R, hdr: &SrdHeader) -> Result<Self> {
+        let mut initiate = SrdInitiate::read_from(reader)?;
+        if hdr.has_version() {
+            initiate.version = Some(reader.read_u16::<LittleEndian>()?);
+        }
+        Ok(initiate)
+    }
 }
 
 impl Message for SrdInitiate {
@@ -37,21 +55,31 @@ impl Message for SrdInitiate {
         Ok(SrdInitiate {
             ciphers: reader.read_u32::<LittleEndian>()?,
             key_size: reader.read_u16::<LittleEndian>()?,
-            reserved: reader.read_u16::<LittleEndian>()?,
+            key_exchanges: reader.read_u16::<LittleEndian>()?,
+            version: None,
         })
     }
 
     fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
         writer.write_u32::<LittleEndian>(self.ciphers)?;
         writer.write_u16::<LittleEndian>(self.key_size)?;
-        writer.write_u16::<LittleEndian>(self.reserved)?;
+        writer.write_u16::<LittleEndian>(self.key_exchanges)?;
+        if let Some(version) = self.version {
+            writer.write_u16::<LittleEndian>(version)?;
+        }
         Ok(())
     }
 }
 
-pub fn new_srd_initiate_msg(seq_num: u8, use_cbt: bool, ciphers: u32, key_size: u16) -> Result<SrdMessage> {
+pub fn new_srd_initiate_msg(
+    seq_num: u8,
+    use_cbt: bool,
+    ciphers: u32,
+    key_size: u16,
+    key_exchanges: u16,
+) -> Result<SrdMessage> {
     let hdr = SrdHeader::new(srd_msg_id::SRD_INITIATE_MSG_ID, seq_num, use_cbt, false);
-    let initiate = SrdInitiate::new(ciphers, key_size)?;
+    let initiate = SrdInitiate::new(ciphers, key_size, key_exchanges)?;
     Ok(SrdMessage::Initiate(hdr, initiate))
 }
 
@@ -62,7 +90,7 @@ mod test {
 
     #[test]
     fn initiate_encoding() {
-        let msg = new_srd_initiate_msg(0, true, 0, 1024).unwrap();
+        let msg = new_srd_initiate_msg(0, true, 0, 1024, 3).unwrap();
         assert_eq!(msg.msg_type(), SRD_INITIATE_MSG_ID);
 
         let mut buffer: Vec<u8> = Vec::new();
@@ -80,4 +108,18 @@ mod test {
             Err(_) => assert!(false),
         }
     }
+
+    #[test]
+    fn versioned_initiate_encoding() {
+        let mut msg = new_srd_initiate_msg(0, false, 0, 256, 3).unwrap();
+        msg.set_version(2);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        msg.write_to(&mut buffer).unwrap();
+        // Header, ciphers, key size, key exchanges and version
+        assert_eq!(buffer.len(), 8 + 8 + 2);
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        assert_eq!(SrdMessage::read_from(&mut cursor).unwrap(), msg);
+    }
 }
@@ -1,6 +1,8 @@
+use byteorder::{ByteOrder, LittleEndian};
+use key_exchange::X25519_KEY_SIZE;
 use messages::*;
 use srd_errors::SrdError;
-use std::io::{Read, Write};
+use std::io::{Cursor, ErrorKind, Read, Write};
 use Result;
 
 pub trait Message {
@@ -17,6 +19,9 @@ pub enum SrdMessage {
     Accept(SrdHeader, SrdAccept),
     Confirm(SrdHeader, SrdConfirm),
     Delegate(SrdHeader, SrdDelegate),
+    Rekey(SrdHeader, SrdRekey),
+    Alert(SrdHeader, SrdAlert),
+    Result(SrdHeader, SrdResult),
 }
 
 impl SrdMessage {
@@ -28,6 +33,9 @@ impl SrdMessage {
             SrdMessage::Accept(hdr, _) => hdr.msg_type(),
             SrdMessage::Confirm(hdr, _) => hdr.msg_type(),
             SrdMessage::Delegate(hdr, _) => hdr.msg_type(),
+            SrdMessage::Rekey(hdr, _) => hdr.msg_type(),
+            SrdMessage::Alert(hdr, _) => hdr.msg_type(),
+            SrdMessage::Result(hdr, _) => hdr.msg_type(),
         }
     }
 
@@ -38,6 +46,9 @@ impl SrdMessage {
             SrdMessage::Accept(hdr, _) => hdr.signature(),
             SrdMessage::Confirm(hdr, _) => hdr.signature(),
             SrdMessage::Delegate(hdr, _) => hdr.signature(),
+            SrdMessage::Rekey(hdr, _) => hdr.signature(),
+            SrdMessage::Alert(hdr, _) => hdr.signature(),
+            SrdMessage::Result(hdr, _) => hdr.signature(),
         }
     }
 
@@ -48,6 +59,9 @@ impl SrdMessage {
             SrdMessage::Accept(hdr, _) => hdr.seq_num(),
             SrdMessage::Confirm(hdr, _) => hdr.seq_num(),
             SrdMessage::Delegate(hdr, _) => hdr.seq_num(),
+            SrdMessage::Rekey(hdr, _) => hdr.seq_num(),
+            SrdMessage::Alert(hdr, _) => hdr.seq_num(),
+            SrdMessage::Result(hdr, _) => hdr.seq_num(),
         }
     }
 
@@ -58,6 +72,9 @@ impl SrdMessage {
             SrdMessage::Accept(hdr, _) => hdr.has_cbt(),
             SrdMessage::Confirm(hdr, _) => hdr.has_cbt(),
             SrdMessage::Delegate(hdr, _) => hdr.has_cbt(),
+            SrdMessage::Rekey(hdr, _) => hdr.has_cbt(),
+            SrdMessage::Alert(hdr, _) => hdr.has_cbt(),
+            SrdMessage::Result(hdr, _) => hdr.has_cbt(),
         }
     }
 
@@ -68,6 +85,9 @@ impl SrdMessage {
             SrdMessage::Accept(hdr, _) => hdr.has_mac(),
             SrdMessage::Confirm(hdr, _) => hdr.has_mac(),
             SrdMessage::Delegate(hdr, _) => hdr.has_mac(),
+            SrdMessage::Rekey(hdr, _) => hdr.has_mac(),
+            SrdMessage::Alert(hdr, _) => hdr.has_mac(),
+            SrdMessage::Result(hdr, _) => hdr.has_mac(),
         }
     }
 
@@ -78,6 +98,9 @@ impl SrdMessage {
             SrdMessage::Accept(hdr, _) => hdr.has_skip(),
             SrdMessage::Confirm(hdr, _) => hdr.has_skip(),
             SrdMessage::Delegate(hdr, _) => hdr.has_skip(),
+            SrdMessage::Rekey(hdr, _) => hdr.has_skip(),
+            SrdMessage::Alert(hdr, _) => hdr.has_skip(),
+            SrdMessage::Result(hdr, _) => hdr.has_skip(),
         }
     }
 
@@ -88,20 +111,32 @@ impl SrdMessage {
             SrdMessage::Accept(_, accept) => Some(accept.mac()),
             SrdMessage::Confirm(_, confirm) => Some(confirm.mac()),
             SrdMessage::Delegate(_, delegate) => Some(delegate.mac()),
+            SrdMessage::Rekey(_, rekey) => Some(rekey.mac()),
+            SrdMessage::Alert(_, _) => None,
+            SrdMessage::Result(_, result) => Some(result.mac()),
         }
     }
 
-    pub fn set_mac(&mut self, mac: &[u8]) -> Result<()> {
+    pub(crate) fn set_mac(&mut self, mac: &[u8]) -> Result<()> {
         match self {
             SrdMessage::Initiate(_, _) => Err(SrdError::Proto("No mac on an initiate message".to_owned())),
             SrdMessage::Offer(_, _) => Err(SrdError::Proto("No mac on an offer message".to_owned())),
             SrdMessage::Accept(_, ref mut accept) => Ok(accept.set_mac(mac)),
             SrdMessage::Confirm(_, ref mut confirm) => Ok(confirm.set_mac(mac)),
             SrdMessage::Delegate(_, ref mut delegate) => Ok(delegate.set_mac(mac)),
+            SrdMessage::Rekey(_, ref mut rekey) => {
+                rekey.set_mac(mac);
+                Ok(())
+            }
+            SrdMessage::Alert(_, _) => Err(SrdError::Proto("No mac on an alert message".to_owned())),
+            SrdMessage::Result(_, ref mut result) => {
+                result.set_mac(mac);
+                Ok(())
+            }
         }
     }
 
-    pub fn set_skip(&mut self) {
+    pub(crate) fn set_skip(&mut self) {
         match self {
             SrdMessage::Initiate(hdr, _) => hdr.add_skip_flag(),
             SrdMessage::Offer(hdr, _) => hdr.add_skip_flag(),
@@ -111,7 +146,43 @@ impl SrdMessage {
         }
     }
 
-    pub fn validate(self) -> Result<Self> {
+    /// Ask for a delegation result as a client, or agree to send one as a server.
+    pub(crate) fn set_result(&mut self) {
+        match self {
+            SrdMessage::Initiate(hdr, _) => hdr.add_result_flag(),
+            SrdMessage::Offer(hdr, _) => hdr.add_result_flag(),
+            _ => {}
+        }
+    }
+
+    /// Tell the server that accepted blob types are understood as a client, or list them as a server.
+    pub(crate) fn set_blob_types(&mut self, blob_types: &[String]) {
+        match self {
+            SrdMessage::Initiate(hdr, _) => hdr.add_blob_types_flag(),
+            SrdMessage::Confirm(hdr, ref mut confirm) => {
+                hdr.add_blob_types_flag();
+                confirm.blob_types = Some(blob_types.to_vec());
+            }
+            _ => {}
+        }
+    }
+
+    /// Advertise the highest protocol version of a client, or the one selected by a server.
+    pub(crate) fn set_version(&mut self, version: u16) {
+        match self {
+            SrdMessage::Initiate(hdr, ref mut initiate) => {
+                hdr.add_version_flag();
+                initiate.version = Some(version);
+            }
+            SrdMessage::Offer(hdr, ref mut offer) => {
+                hdr.add_version_flag();
+                offer.version = Some(version);
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn validate(self) -> Result<Self> {
         match &self {
             SrdMessage::Initiate(hdr, initiate) => {
                 // No MAC in that message
@@ -124,9 +195,17 @@ impl SrdMessage {
                 }
             }
 
-            SrdMessage::Offer(hdr, _offer) => {
+            SrdMessage::Offer(hdr, offer) => {
                 // No MAC in that message
                 hdr.validate_flags(false)?;
+
+                if hdr.has_x25519() && hdr.has_dh_group() {
+                    return Err(SrdError::Proto("An offer can't select both X25519 and a DH group".to_owned()));
+                }
+
+                if hdr.has_x25519() && offer.key_size() != X25519_KEY_SIZE {
+                    return Err(SrdError::InvalidKeySize);
+                }
             }
 
             SrdMessage::Accept(hdr, _accept) => {
@@ -143,6 +222,21 @@ impl SrdMessage {
                 // MAC has to be set
                 hdr.validate_flags(true)?;
             }
+
+            SrdMessage::Rekey(hdr, _rekey) => {
+                // MAC has to be set
+                hdr.validate_flags(true)?;
+            }
+
+            SrdMessage::Alert(hdr, _alert) => {
+                // No MAC in that message
+                hdr.validate_flags(false)?;
+            }
+
+            SrdMessage::Result(hdr, _result) => {
+                // MAC has to be set
+                hdr.validate_flags(true)?;
+            }
         }
         Ok(self)
     }
@@ -156,11 +250,11 @@ impl Message for SrdMessage {
         let header = SrdHeader::read_from(&mut reader)?;
         match header.msg_type() {
             srd_msg_id::SRD_INITIATE_MSG_ID => {
-                let initiate = SrdInitiate::read_from(&mut reader)?;
+                let initiate = SrdInitiate::read_with_header(&mut reader, &header)?;
                 Ok(SrdMessage::Initiate(header, initiate).validate()?)
             }
             srd_msg_id::SRD_OFFER_MSG_ID => {
-                let offer = SrdOffer::read_from(&mut reader)?;
+                let offer = SrdOffer::read_with_header(&mut reader, &header)?;
                 Ok(SrdMessage::Offer(header, offer).validate()?)
             }
             srd_msg_id::SRD_ACCEPT_MSG_ID => {
@@ -168,13 +262,25 @@ impl Message for SrdMessage {
                 Ok(SrdMessage::Accept(header, accept).validate()?)
             }
             srd_msg_id::SRD_CONFIRM_MSG_ID => {
-                let confirm = SrdConfirm::read_from(&mut reader)?;
+                let confirm = SrdConfirm::read_with_header(&mut reader, &header)?;
                 Ok(SrdMessage::Confirm(header, confirm).validate()?)
             }
             srd_msg_id::SRD_DELEGATE_MSG_ID => {
                 let delegate = SrdDelegate::read_from(&mut reader)?;
                 Ok(SrdMessage::Delegate(header, delegate).validate()?)
             }
+            srd_msg_id::SRD_REKEY_MSG_ID => {
+                let rekey = SrdRekey::read_from(&mut reader)?;
+                Ok(SrdMessage::Rekey(header, rekey).validate()?)
+            }
+            srd_msg_id::SRD_ALERT_MSG_ID => {
+                let alert = SrdAlert::read_from(&mut reader)?;
+                Ok(SrdMessage::Alert(header, alert).validate()?)
+            }
+            srd_msg_id::SRD_RESULT_MSG_ID => {
+                let result = SrdResult::read_from(&mut reader)?;
+                Ok(SrdMessage::Result(header, result).validate()?)
+            }
             _ => Err(SrdError::UnknownMsgType),
         }
     }
@@ -206,6 +312,21 @@ impl Message for SrdMessage {
                 delegate.write_to(&mut writer)?;
                 Ok(())
             }
+            SrdMessage::Rekey(hdr, rekey) => {
+                hdr.write_to(&mut writer)?;
+                rekey.write_to(&mut writer)?;
+                Ok(())
+            }
+            SrdMessage::Alert(hdr, alert) => {
+                hdr.write_to(&mut writer)?;
+                alert.write_to(&mut writer)?;
+                Ok(())
+            }
+            SrdMessage::Result(hdr, result) => {
+                hdr.write_to(&mut writer)?;
+                result.write_to(&mut writer)?;
+                Ok(())
+            }
         }
     }
 }
@@ -216,15 +337,70 @@ pub trait ReadMac {
 
 impl<T: Read> ReadMac for T {
     fn read_mac(&mut self, mac: &mut [u8]) -> Result<()> {
-        let mut v = Vec::new();
-        self.read_to_end(&mut v)?;
+        // The MAC is the last field of a message, anything after it belongs to the next one
+        self.read_exact(mac).map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => SrdError::InvalidDataLength,
+            _ => SrdError::Io(e),
+        })
+    }
+}
 
-        if v.len() >= mac.len() {
-            let len_to_remove = v.len() - mac.len();
-            mac.copy_from_slice(&v.split_off(len_to_remove));
-            Ok(())
-        } else {
-            Err(SrdError::InvalidDataLength)
-        }
+pub enum MessageSize {
+    /// Size of the whole message
+    Complete(usize),
+    /// Bytes needed before the size of the message can be known
+    Partial(usize),
+}
+
+/// Size of the message starting at the beginning of `buffer`.
+///
+/// Messages don't carry their length, it is computed from the header and from the size fields of the body.
+pub fn message_size(buffer: &[u8]) -> Result<MessageSize> {
+    if buffer.len() < SRD_HEADER_SIZE {
+        return Ok(MessageSize::Partial(SRD_HEADER_SIZE));
     }
+
+    // Reads a size field, or asks for the bytes up to its end
+    let field = |offset: usize, size: usize| match buffer.get(offset..offset + size) {
+        Some(field) => Ok(LittleEndian::read_uint(field, size) as usize),
+        None => Err(MessageSize::Partial(offset + size)),
+    };
+
+    let header = SrdHeader::read_from(&mut Cursor::new(buffer))?;
+    // Protocol version at the end of initiate and offer messages
+    let version_size = if header.has_version() { 2 } else { 0 };
+    let size = match header.msg_type() {
+        srd_msg_id::SRD_INITIATE_MSG_ID => Ok(SRD_HEADER_SIZE + 8 + version_size),
+        // Ciphers and key size, then public key and nonce
+        srd_msg_id::SRD_OFFER_MSG_ID if header.has_x25519() => {
+            field(SRD_HEADER_SIZE + 4, 2).map(|key_size| SRD_HEADER_SIZE + 6 + key_size + 32 + version_size)
+        }
+        // Ciphers, key size, group id and generator size, then generator, prime, public key and nonce
+        srd_msg_id::SRD_OFFER_MSG_ID if header.has_dh_group() => field(SRD_HEADER_SIZE + 4, 2).and_then(|key_size| {
+            field(SRD_HEADER_SIZE + 8, 2)
+                .map(|generator_size| SRD_HEADER_SIZE + 10 + generator_size + 2 * key_size + 32 + version_size)
+        }),
+        // Ciphers, key size and generator, then prime, public key and nonce
+        srd_msg_id::SRD_OFFER_MSG_ID => {
+            field(SRD_HEADER_SIZE + 4, 2).map(|key_size| SRD_HEADER_SIZE + 8 + 2 * key_size + 32 + version_size)
+        }
+        // Cipher, key size and reserved, then public key, nonce, CBT and MAC
+        srd_msg_id::SRD_ACCEPT_MSG_ID => field(SRD_HEADER_SIZE + 4, 2).map(|key_size| SRD_HEADER_SIZE + 8 + key_size + 96),
+        // CBT and blob types size, then blob types and MAC
+        srd_msg_id::SRD_CONFIRM_MSG_ID if header.has_blob_types() => {
+            field(SRD_HEADER_SIZE + 32, 2).map(|size| SRD_HEADER_SIZE + 34 + size + 32)
+        }
+        srd_msg_id::SRD_CONFIRM_MSG_ID => Ok(SRD_HEADER_SIZE + 64),
+        // Blob size, then encrypted blob and MAC
+        srd_msg_id::SRD_DELEGATE_MSG_ID => field(SRD_HEADER_SIZE, 4).map(|size| SRD_HEADER_SIZE + 4 + size + 32),
+        // Sequence, nonce and MAC
+        srd_msg_id::SRD_REKEY_MSG_ID => Ok(SRD_HEADER_SIZE + 72),
+        // Code and text size, then text
+        srd_msg_id::SRD_ALERT_MSG_ID => field(SRD_HEADER_SIZE + 2, 2).map(|size| SRD_HEADER_SIZE + 4 + size),
+        // Status, reserved and payload size, then payload and MAC
+        srd_msg_id::SRD_RESULT_MSG_ID => field(SRD_HEADER_SIZE + 4, 4).map(|size| SRD_HEADER_SIZE + 8 + size + 32),
+        _ => return Err(SrdError::UnknownMsgType),
+    };
+
+    Ok(size.map(MessageSize::Complete).unwrap_or_else(|partial| partial))
 }
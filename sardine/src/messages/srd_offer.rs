@@ -1,4 +1,6 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use dh_group::DhGroup;
+use key_exchange::X25519_KEY_SIZE;
 use messages::{expand_start, srd_msg_id, Message, SrdHeader, SrdMessage};
 use std::io::{Read, Write};
 use Result;
@@ -7,31 +9,54 @@ use Result;
 pub struct SrdOffer {
     pub ciphers: u32,
     key_size: u16,
+    pub group_id: Option<u16>,
     pub generator: Vec<u8>,
     pub prime: Vec<u8>,
     pub public_key: Vec<u8>,
     pub nonce: [u8; 32],
+    pub version: Option<u16>,
 }
 
 impl SrdOffer {
     pub fn key_size(&self) -> u16 {
         self.key_size
     }
-}
 
-impl Message for SrdOffer {
-    fn read_from<R: Read>(reader: &mut R) -> Result<Self>
-        where
-            Self: Sized,
-    {
+    /// Read an offer whose layout is given by the header flags.
+    ///
+    /// An X25519 offer has no generator nor prime. An offer identifying its DH group has the group id and the
+    /// generator size before the generator, which is otherwise 2 bytes long. The protocol version selected by the
+    /// server follows the nonce when the header has the version flag.
+    pub fn read_with_header<R: Read>(reader: &mut R, hdr: &SrdHeader) -> Result<Self> {
+        let mut offer = SrdOffer::read_body(reader, hdr.has_x25519(), hdr.has_dh_group())?;
+        if hdr.has_version() {
+            offer.version = Some(reader.read_u16::<LittleEndian>()?);
+        }
+        Ok(offer)
+    }
+
+    fn read_body<R: Read>(reader: &mut R, x25519: bool, dh_group: bool) -> Result<Self> {
         let ciphers = reader.read_u32::<LittleEndian>()?;
         let key_size = reader.read_u16::<LittleEndian>()?;
 
-        let mut generator = vec![0u8; 2];
-        let mut prime = vec![0u8; key_size as usize];
+        let mut group_id = None;
+        let mut generator = Vec::new();
+        let mut prime = Vec::new();
+        if !x25519 {
+            let generator_size = if dh_group {
+                group_id = Some(reader.read_u16::<LittleEndian>()?);
+                reader.read_u16::<LittleEndian>()?
+            } else {
+                2
+            };
+
+            generator = vec![0u8; generator_size as usize];
+            prime = vec![0u8; key_size as usize];
+            reader.read_exact(&mut generator)?;
+            reader.read_exact(&mut prime)?;
+        }
+
         let mut public_key = vec![0u8; key_size as usize];
-        reader.read_exact(&mut generator)?;
-        reader.read_exact(&mut prime)?;
         reader.read_exact(&mut public_key)?;
 
         let mut nonce = [0u8; 32];
@@ -40,59 +65,130 @@ impl Message for SrdOffer {
         Ok(SrdOffer {
             ciphers,
             key_size,
+            group_id,
             generator,
             prime,
             public_key,
             nonce,
+            version: None,
         })
     }
+}
+
+impl Message for SrdOffer {
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self>
+        where
+            Self: Sized,
+    {
+        SrdOffer::read_body(reader, false, false)
+    }
 
     fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
         writer.write_u32::<LittleEndian>(self.ciphers)?;
         writer.write_u16::<LittleEndian>(self.key_size)?;
+        if let Some(group_id) = self.group_id {
+            writer.write_u16::<LittleEndian>(group_id)?;
+            writer.write_u16::<LittleEndian>(self.generator.len() as u16)?;
+        }
         writer.write_all(&self.generator)?;
         writer.write_all(&self.prime)?;
         writer.write_all(&self.public_key)?;
         writer.write_all(&self.nonce)?;
+        if let Some(version) = self.version {
+            writer.write_u16::<LittleEndian>(version)?;
+        }
 
         Ok(())
     }
 }
 
+/// Offer of a group without its id, for clients that don't know about group ids.
 pub fn new_srd_offer_msg(
     seq_num: u8,
     use_cbt: bool,
     ciphers: u32,
-    key_size: u16,
-    mut generator: Vec<u8>,
-    mut prime: Vec<u8>,
+    group: DhGroup,
     mut public_key: Vec<u8>,
     nonce: [u8; 32],
 ) -> SrdMessage {
+    let key_size = group.key_size();
+    let mut generator = group.generator().to_vec();
     expand_start(&mut generator, 2);
-    expand_start(&mut prime, (key_size / 8) as usize);
-    expand_start(&mut public_key, (key_size / 8) as usize);
+    expand_start(&mut public_key, key_size as usize);
 
     let hdr = SrdHeader::new(srd_msg_id::SRD_OFFER_MSG_ID, seq_num, use_cbt, false);
     let offer = SrdOffer {
         ciphers,
         key_size,
+        group_id: None,
         generator,
-        prime,
+        prime: group.prime().to_vec(),
         public_key,
         nonce,
+        version: None,
+    };
+    SrdMessage::Offer(hdr, offer)
+}
+
+pub fn new_srd_dh_group_offer_msg(
+    seq_num: u8,
+    use_cbt: bool,
+    ciphers: u32,
+    group: DhGroup,
+    mut public_key: Vec<u8>,
+    nonce: [u8; 32],
+) -> SrdMessage {
+    expand_start(&mut public_key, group.key_size() as usize);
+
+    let mut hdr = SrdHeader::new(srd_msg_id::SRD_OFFER_MSG_ID, seq_num, use_cbt, false);
+    hdr.add_dh_group_flag();
+    let offer = SrdOffer {
+        ciphers,
+        key_size: group.key_size(),
+        group_id: Some(group.id()),
+        generator: group.generator().to_vec(),
+        prime: group.prime().to_vec(),
+        public_key,
+        nonce,
+        version: None,
+    };
+    SrdMessage::Offer(hdr, offer)
+}
+
+pub fn new_srd_x25519_offer_msg(
+    seq_num: u8,
+    use_cbt: bool,
+    ciphers: u32,
+    public_key: [u8; 32],
+    nonce: [u8; 32],
+) -> SrdMessage {
+    let mut hdr = SrdHeader::new(srd_msg_id::SRD_OFFER_MSG_ID, seq_num, use_cbt, false);
+    hdr.add_x25519_flag();
+    let offer = SrdOffer {
+        ciphers,
+        key_size: X25519_KEY_SIZE,
+        group_id: None,
+        generator: Vec::new(),
+        prime: Vec::new(),
+        public_key: public_key.to_vec(),
+        nonce,
+        version: None,
     };
     SrdMessage::Offer(hdr, offer)
 }
 
 #[cfg(test)]
 mod test {
-    use messages::{new_srd_offer_msg, srd_msg_id::SRD_OFFER_MSG_ID, Message, SrdMessage, SRD_SIGNATURE};
+    use dh_group::DhGroup;
+    use messages::{
+        new_srd_dh_group_offer_msg, new_srd_offer_msg, new_srd_x25519_offer_msg, srd_msg_id::SRD_OFFER_MSG_ID,
+        Message, SrdMessage, SRD_SIGNATURE,
+    };
     use std;
 
     #[test]
     fn offer_encoding() {
-        let msg = new_srd_offer_msg(1, true, 0, 256, vec![0, 0], vec![0u8; 256], vec![0u8; 256], [0u8; 32]);
+        let msg = new_srd_offer_msg(1, true, 0, DhGroup::Legacy2048, vec![0u8; 256], [0u8; 32]);
         assert_eq!(msg.msg_type(), SRD_OFFER_MSG_ID);
 
         let mut buffer: Vec<u8> = Vec::new();
@@ -110,4 +206,40 @@ mod test {
             Err(_) => assert!(false),
         }
     }
+
+    #[test]
+    fn x25519_offer_encoding() {
+        let msg = new_srd_x25519_offer_msg(1, false, 0, [7u8; 32], [0u8; 32]);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        msg.write_to(&mut buffer).unwrap();
+        // Header, ciphers, key size, public key and nonce
+        assert_eq!(buffer.len(), 8 + 6 + 32 + 32);
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        assert_eq!(SrdMessage::read_from(&mut cursor).unwrap(), msg);
+
+        // The selected version follows the nonce
+        let mut msg = msg;
+        msg.set_version(2);
+        let mut buffer: Vec<u8> = Vec::new();
+        msg.write_to(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), 8 + 6 + 32 + 32 + 2);
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        assert_eq!(SrdMessage::read_from(&mut cursor).unwrap(), msg);
+    }
+
+    #[test]
+    fn dh_group_offer_encoding() {
+        let msg = new_srd_dh_group_offer_msg(1, false, 0, DhGroup::Ffdhe3072, vec![1u8; 384], [0u8; 32]);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        msg.write_to(&mut buffer).unwrap();
+        // Header, ciphers, key size, group id, generator size and a 1 byte generator, prime, public key and nonce
+        assert_eq!(buffer.len(), 8 + 10 + 1 + 2 * 384 + 32);
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        assert_eq!(SrdMessage::read_from(&mut cursor).unwrap(), msg);
+    }
 }
@@ -0,0 +1,79 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::Read;
+use std::io::Write;
+
+use messages::{srd_message::ReadMac, srd_msg_id, Message, SrdHeader, SrdMessage};
+use Result;
+
+/// Rotation of the keys of one direction of a session. Records from `sequence` on are sealed with the next keys.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SrdRekey {
+    pub sequence: u64,
+    pub nonce: [u8; 32],
+    mac: [u8; 32],
+}
+
+impl SrdRekey {
+    pub fn mac(&self) -> &[u8] {
+        &self.mac
+    }
+
+    pub fn set_mac(&mut self, mac: &[u8]) {
+        self.mac.clone_from_slice(mac);
+    }
+}
+
+impl Message for SrdRekey {
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self>
+        where
+            Self: Sized,
+    {
+        let sequence = reader.read_u64::<LittleEndian>()?;
+
+        let mut nonce = [0u8; 32];
+        reader.read_exact(&mut nonce)?;
+
+        let mut mac = [0u8; 32];
+        reader.read_mac(&mut mac)?;
+
+        Ok(SrdRekey { sequence, nonce, mac })
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u64::<LittleEndian>(self.sequence)?;
+        writer.write_all(&self.nonce)?;
+        writer.write_all(&self.mac)?;
+        Ok(())
+    }
+}
+
+pub fn new_srd_rekey_msg(sequence: u64, nonce: [u8; 32]) -> SrdMessage {
+    let hdr = SrdHeader::new(srd_msg_id::SRD_REKEY_MSG_ID, 0, false, true);
+    let rekey = SrdRekey {
+        sequence,
+        nonce,
+        mac: [0u8; 32],
+    };
+
+    SrdMessage::Rekey(hdr, rekey)
+}
+
+#[cfg(test)]
+mod test {
+    use messages::{new_srd_rekey_msg, srd_msg_id::SRD_REKEY_MSG_ID, Message, SrdMessage};
+    use std;
+
+    #[test]
+    fn rekey_encoding() {
+        let msg = new_srd_rekey_msg(42, [7u8; 32]);
+        assert_eq!(msg.msg_type(), SRD_REKEY_MSG_ID);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        msg.write_to(&mut buffer).unwrap();
+        // Header, sequence, nonce and MAC
+        assert_eq!(buffer.len(), 8 + 8 + 32 + 32);
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        assert_eq!(SrdMessage::read_from(&mut cursor).unwrap(), msg);
+    }
+}
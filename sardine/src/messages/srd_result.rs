@@ -0,0 +1,176 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::Read;
+use std::io::Write;
+
+use messages::{srd_message::ReadMac, srd_msg_id, Message, SrdHeader, SrdMessage};
+use srd_errors::SrdError;
+use Result;
+
+const RESULT_SUCCESS: u16 = 0;
+const RESULT_FAILURE: u16 = 1;
+const RESULT_PAYLOAD_SIZE_LIMIT: u32 = 16 * 1024; // Limit RESULT payloads to 16 Kb.
+
+/// Outcome of the delegation as decided by the server, whether the delegated credentials were accepted.
+///
+/// The payload is authenticated but not encrypted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
+pub struct DelegationResult {
+    success: bool,
+    payload: Vec<u8>,
+}
+
+impl DelegationResult {
+    pub fn success(payload: Vec<u8>) -> DelegationResult {
+        DelegationResult { success: true, payload }
+    }
+
+    pub fn failure(payload: Vec<u8>) -> DelegationResult {
+        DelegationResult {
+            success: false,
+            payload,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.success
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+}
+
+/// Last message of a handshake with a delegation result, sent by the server once it has checked the blob.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SrdResult {
+    status: u16,
+    reserved: u16,
+    payload: Vec<u8>,
+    mac: [u8; 32],
+}
+
+impl SrdResult {
+    pub fn mac(&self) -> &[u8] {
+        &self.mac
+    }
+
+    pub fn set_mac(&mut self, mac: &[u8]) {
+        self.mac.clone_from_slice(mac);
+    }
+
+    pub fn result(&self) -> Result<DelegationResult> {
+        match self.status {
+            RESULT_SUCCESS => Ok(DelegationResult::success(self.payload.clone())),
+            RESULT_FAILURE => Ok(DelegationResult::failure(self.payload.clone())),
+            status => Err(SrdError::Proto(format!("Unknown delegation result status {}", status))),
+        }
+    }
+}
+
+impl Message for SrdResult {
+    fn read_from<R: Read>(reader: &mut R) -> Result<Self>
+        where
+            Self: Sized,
+    {
+        let status = reader.read_u16::<LittleEndian>()?;
+        let reserved = reader.read_u16::<LittleEndian>()?;
+        let payload_size = reader.read_u32::<LittleEndian>()?;
+
+        if payload_size > RESULT_PAYLOAD_SIZE_LIMIT {
+            return Err(SrdError::InvalidDataLength);
+        }
+
+        let mut payload = vec![0u8; payload_size as usize];
+        reader.read_exact(&mut payload)?;
+
+        let mut mac = [0u8; 32];
+        reader.read_mac(&mut mac)?;
+
+        Ok(SrdResult {
+            status,
+            reserved,
+            payload,
+            mac,
+        })
+    }
+
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        if self.payload.len() > RESULT_PAYLOAD_SIZE_LIMIT as usize {
+            return Err(SrdError::InvalidDataLength);
+        }
+
+        writer.write_u16::<LittleEndian>(self.status)?;
+        writer.write_u16::<LittleEndian>(self.reserved)?;
+        writer.write_u32::<LittleEndian>(self.payload.len() as u32)?;
+        writer.write_all(&self.payload)?;
+        writer.write_all(&self.mac)?;
+        Ok(())
+    }
+}
+
+pub fn new_srd_result_msg(seq_num: u8, use_cbt: bool, result: &DelegationResult) -> SrdMessage {
+    let hdr = SrdHeader::new(srd_msg_id::SRD_RESULT_MSG_ID, seq_num, use_cbt, true);
+    let result = SrdResult {
+        status: if result.is_success() {
+            RESULT_SUCCESS
+        } else {
+            RESULT_FAILURE
+        },
+        reserved: 0,
+        payload: result.payload().to_vec(),
+        mac: [0u8; 32],
+    };
+
+    SrdMessage::Result(hdr, result)
+}
+
+#[cfg(test)]
+mod test {
+    use byteorder::{LittleEndian, WriteBytesExt};
+    use messages::{new_srd_result_msg, srd_msg_id::SRD_RESULT_MSG_ID, DelegationResult, Message, SrdMessage, SrdResult};
+    use srd_errors::SrdError;
+    use std;
+
+    #[test]
+    fn result_encoding() {
+        let msg = new_srd_result_msg(5, false, &DelegationResult::failure(b"wrong password".to_vec()));
+        assert_eq!(msg.msg_type(), SRD_RESULT_MSG_ID);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        msg.write_to(&mut buffer).unwrap();
+        // Header, status, reserved, payload size, payload and MAC
+        assert_eq!(buffer.len(), 8 + 8 + 14 + 32);
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        let read = SrdMessage::read_from(&mut cursor).unwrap();
+        assert_eq!(read, msg);
+        match read {
+            SrdMessage::Result(_, result) => {
+                let result = result.result().unwrap();
+                assert!(!result.is_success());
+                assert_eq!(result.payload(), b"wrong password");
+            }
+            _ => panic!("not a result"),
+        }
+    }
+
+    #[test]
+    fn result_payload_size_limit() {
+        // Status, reserved and an oversized payload size with no payload behind it
+        let mut buffer: Vec<u8> = vec![0, 0, 0, 0];
+        buffer.write_u32::<LittleEndian>(u32::MAX).unwrap();
+
+        let mut cursor = std::io::Cursor::new(buffer.as_slice());
+        match SrdResult::read_from(&mut cursor) {
+            Err(SrdError::InvalidDataLength) => {}
+            _ => panic!("oversized payload accepted"),
+        }
+
+        let msg = new_srd_result_msg(5, false, &DelegationResult::success(vec![0u8; 16 * 1024 + 1]));
+        match msg.write_to(&mut Vec::new()) {
+            Err(SrdError::InvalidDataLength) => {}
+            _ => panic!("oversized payload written"),
+        }
+    }
+}
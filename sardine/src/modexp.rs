@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crypto_bigint::modular::runtime_mod::{DynResidue, DynResidueParams};
+use crypto_bigint::subtle::{ConditionallySelectable, ConstantTimeEq};
+use crypto_bigint::{Limb, Uint, Word, U2048, U3072, U4096, U6144, U8192};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use zeroize::{Zeroize, Zeroizing};
+
+use dh_group::DhGroup;
+use srd_errors::SrdError;
+use Result;
+
+// Fixed-base tables split exponents in windows of 4 bits
+const WINDOW_BITS: usize = 4;
+const WINDOW_SIZE: usize = 1 << WINDOW_BITS;
+
+// Runs `$function::<LIMBS>` with the narrowest width holding `$size` bytes. Widths are those of the DH groups,
+// anything else is rounded up to the next one.
+macro_rules! with_width {
+    ($size:expr, $function:ident($($arg:expr),*)) => {
+        if $size <= U2048::BYTES {
+            Ok($function::<{ U2048::LIMBS }>($($arg),*))
+        } else if $size <= U3072::BYTES {
+            Ok($function::<{ U3072::LIMBS }>($($arg),*))
+        } else if $size <= U4096::BYTES {
+            Ok($function::<{ U4096::LIMBS }>($($arg),*))
+        } else if $size <= U6144::BYTES {
+            Ok($function::<{ U6144::LIMBS }>($($arg),*))
+        } else if $size <= U8192::BYTES {
+            Ok($function::<{ U8192::LIMBS }>($($arg),*))
+        } else {
+            Err(SrdError::InvalidKeySize)
+        }
+    };
+}
+
+/// Compute `base^exponent mod modulus` in constant time with respect to the exponent.
+///
+/// The exponent is a secret given as big endian bytes. The computation runs on fixed-width Montgomery residues,
+/// whose width only depends on the size of the modulus, and goes through every bit of the exponent whatever its
+/// value. Only the length of the exponent shows in its timing. The base and the modulus are public. The result is
+/// big endian, as long as the modulus.
+pub fn modpow(base: &BigUint, exponent: &[u8], modulus: &BigUint) -> Result<Vec<u8>> {
+    let modulus = modulus_bytes(modulus)?;
+    let base = (base % BigUint::from_bytes_be(&modulus)).to_bytes_be();
+
+    let width = std::cmp::max(modulus.len(), exponent.len());
+    let result = Zeroizing::new(with_width!(width, modpow_fixed(&base, exponent, &modulus))?);
+    Ok(result[result.len() - modulus.len()..].to_vec())
+}
+
+/// Powers of a fixed base, to raise it with multiplications only.
+///
+/// Exponents are split in windows of 4 bits, row `i` of the table holds `base^(j * 16^i)` for every window value
+/// `j`. Raising the base is the product of one entry per row, each selected in constant time, which saves all the
+/// squarings of `modpow`.
+pub struct FixedBase {
+    modulus: Vec<u8>,
+    exponent_size: usize,
+    table: Vec<Word>,
+}
+
+impl FixedBase {
+    /// Precompute the powers of `base` for exponents of up to `exponent_size` bytes.
+    pub fn new(base: &BigUint, modulus: &BigUint, exponent_size: usize) -> Result<FixedBase> {
+        let modulus = modulus_bytes(modulus)?;
+        let base = (base % BigUint::from_bytes_be(&modulus)).to_bytes_be();
+        let rows = exponent_size * 8 / WINDOW_BITS;
+
+        let table = with_width!(modulus.len(), build_table(&base, &modulus, rows))?;
+        Ok(FixedBase {
+            modulus,
+            exponent_size,
+            table,
+        })
+    }
+
+    /// Compute `base^exponent mod modulus`, with the same guarantees as `modpow`.
+    pub fn pow(&self, exponent: &[u8]) -> Result<Vec<u8>> {
+        if exponent.len() > self.exponent_size {
+            return Err(SrdError::InvalidKeySize);
+        }
+
+        // Every row is used, whatever the length of the exponent
+        let mut padded = Zeroizing::new(vec![0u8; self.exponent_size]);
+        let start = padded.len() - exponent.len();
+        padded[start..].copy_from_slice(exponent);
+
+        let result = with_width!(self.modulus.len(), fixed_base_pow(&self.table, &padded, &self.modulus))?;
+        Ok(result[result.len() - self.modulus.len()..].to_vec())
+    }
+}
+
+/// Table of the generator of a group, for its private exponents. It is computed on first use and shared afterwards.
+pub fn generator_table(group: DhGroup) -> Result<Arc<FixedBase>> {
+    static TABLES: OnceLock<Mutex<HashMap<DhGroup, Arc<FixedBase>>>> = OnceLock::new();
+
+    let mut tables = TABLES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    if let Some(table) = tables.get(&group) {
+        return Ok(table.clone());
+    }
+
+    let generator = BigUint::from_bytes_be(group.generator());
+    let prime = BigUint::from_bytes_be(group.prime());
+    let table = Arc::new(FixedBase::new(&generator, &prime, group.exponent_size())?);
+    tables.insert(group, table.clone());
+    Ok(table)
+}
+
+fn modulus_bytes(modulus: &BigUint) -> Result<Vec<u8>> {
+    // Montgomery arithmetic needs an odd modulus
+    if modulus.is_even() || modulus <= &BigUint::from(1u32) {
+        return Err(SrdError::Crypto);
+    }
+    Ok(modulus.to_bytes_be())
+}
+
+fn modpow_fixed<const LIMBS: usize>(base: &[u8], exponent: &[u8], modulus: &[u8]) -> Vec<u8> {
+    let params = DynResidueParams::new(&from_be_bytes::<LIMBS>(modulus));
+    let base = DynResidue::new(&from_be_bytes::<LIMBS>(base), params);
+
+    let mut exponent_value = from_be_bytes::<LIMBS>(exponent);
+    let result = base.pow_bounded_exp(&exponent_value, exponent.len() * 8);
+    exponent_value.zeroize();
+
+    let mut value = result.retrieve();
+    let bytes = to_be_bytes(&value);
+    value.zeroize();
+    bytes
+}
+
+fn build_table<const LIMBS: usize>(base: &[u8], modulus: &[u8], rows: usize) -> Vec<Word> {
+    let params = DynResidueParams::new(&from_be_bytes::<LIMBS>(modulus));
+    let mut row_base = DynResidue::new(&from_be_bytes::<LIMBS>(base), params);
+
+    let mut table = Vec::with_capacity(rows * WINDOW_SIZE * LIMBS);
+    for _ in 0..rows {
+        let mut entry = DynResidue::one(params);
+        for _ in 0..WINDOW_SIZE {
+            table.extend_from_slice(entry.as_montgomery().as_words());
+            entry = entry.mul(&row_base);
+        }
+        // row_base^16, the base of the next row
+        row_base = entry;
+    }
+    table
+}
+
+fn fixed_base_pow<const LIMBS: usize>(table: &[Word], exponent: &[u8], modulus: &[u8]) -> Vec<u8> {
+    let params = DynResidueParams::new(&from_be_bytes::<LIMBS>(modulus));
+    let mut result = DynResidue::one(params);
+
+    // Windows from the least significant one, matching the rows of the table
+    let windows = exponent.iter().rev().flat_map(|byte| [byte & 0x0F, byte >> WINDOW_BITS]);
+    for (row, window) in table.chunks(WINDOW_SIZE * LIMBS).zip(windows) {
+        let mut selected = Uint::<LIMBS>::ZERO;
+        for (value, entry) in row.chunks(LIMBS).enumerate() {
+            let mut words = [0 as Word; LIMBS];
+            words.copy_from_slice(entry);
+            selected = Uint::conditional_select(&selected, &Uint::from_words(words), (value as u8).ct_eq(&window));
+        }
+        result = result.mul(&DynResidue::from_montgomery(selected, params));
+    }
+
+    to_be_bytes(&result.retrieve())
+}
+
+fn from_be_bytes<const LIMBS: usize>(bytes: &[u8]) -> Uint<LIMBS> {
+    // Exponents go through here
+    let mut padded = Zeroizing::new(vec![0u8; LIMBS * Limb::BYTES]);
+    let start = padded.len() - bytes.len();
+    padded[start..].copy_from_slice(bytes);
+    Uint::from_be_slice(&padded)
+}
+
+fn to_be_bytes<const LIMBS: usize>(value: &Uint<LIMBS>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(LIMBS * Limb::BYTES);
+    for word in value.as_words().iter().rev() {
+        bytes.extend_from_slice(&word.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod test {
+    use dh_group::DhGroup;
+    use modexp::{generator_table, modpow, FixedBase};
+    use num_bigint::BigUint;
+    use rand::{rngs::OsRng, RngCore};
+
+    #[test]
+    fn modpow_matches_num_bigint() {
+        for group in [DhGroup::Legacy2048, DhGroup::Legacy4096, DhGroup::Legacy8192].iter() {
+            let generator = BigUint::from_bytes_be(group.generator());
+            let prime = BigUint::from_bytes_be(group.prime());
+
+            let mut exponent = vec![0u8; group.key_size() as usize];
+            OsRng.fill_bytes(&mut exponent);
+            // Shared secrets are computed from the peer public key, any element of the group
+            let public_key = generator.modpow(&BigUint::from(0x1234_5678u32), &prime);
+
+            let expected = public_key.modpow(&BigUint::from_bytes_be(&exponent), &prime);
+            let result = modpow(&public_key, &exponent, &prime).unwrap();
+            assert_eq!(result.len(), group.key_size() as usize);
+            assert_eq!(BigUint::from_bytes_be(&result), expected);
+        }
+    }
+
+    #[test]
+    fn modpow_edge_cases() {
+        let prime = BigUint::from_bytes_be(DhGroup::Ffdhe2048.prime());
+        let generator = BigUint::from(2u32);
+
+        assert_eq!(BigUint::from_bytes_be(&modpow(&generator, &[0], &prime).unwrap()), BigUint::from(1u32));
+        assert_eq!(BigUint::from_bytes_be(&modpow(&generator, &[1], &prime).unwrap()), generator);
+        assert_eq!(BigUint::from_bytes_be(&modpow(&generator, &[], &prime).unwrap()), BigUint::from(1u32));
+
+        // Fermat's little theorem
+        let exponent = (&prime - 1u32).to_bytes_be();
+        assert_eq!(BigUint::from_bytes_be(&modpow(&generator, &exponent, &prime).unwrap()), BigUint::from(1u32));
+
+        // Small moduli work as well, even ones don't
+        assert_eq!(modpow(&BigUint::from(4u32), &[13], &BigUint::from(497u32)).unwrap(), vec![0x01, 0xBD]);
+        assert!(modpow(&generator, &[1], &BigUint::from(496u32)).is_err());
+    }
+
+    #[test]
+    fn fixed_base_matches_modpow() {
+        for group in [DhGroup::Ffdhe2048, DhGroup::Legacy4096].iter() {
+            let generator = BigUint::from_bytes_be(group.generator());
+            let prime = BigUint::from_bytes_be(group.prime());
+            let table = generator_table(*group).unwrap();
+
+            let mut exponent = vec![0u8; group.exponent_size()];
+            OsRng.fill_bytes(&mut exponent);
+            assert_eq!(table.pow(&exponent).unwrap(), modpow(&generator, &exponent, &prime).unwrap());
+
+            // Shorter exponents are padded, longer ones don't fit in the table
+            assert_eq!(table.pow(&exponent[1..]).unwrap(), modpow(&generator, &exponent[1..], &prime).unwrap());
+            assert!(table.pow(&vec![1u8; group.exponent_size() + 1]).is_err());
+        }
+
+        let table = FixedBase::new(&BigUint::from(4u32), &BigUint::from(497u32), 1).unwrap();
+        assert_eq!(table.pow(&[13]).unwrap(), vec![0x01, 0xBD]);
+        assert_eq!(table.pow(&[0]).unwrap(), vec![0x00, 0x01]);
+    }
+}
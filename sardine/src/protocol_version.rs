@@ -0,0 +1,35 @@
+/// Version of the SRD protocol negotiated by a handshake.
+///
+/// Clients advertise the highest version they support in the Initiate message and servers select the highest one
+/// they share in the Offer. Peers that don't advertise any version use version 1, the format of the messages
+/// before the negotiation. Both fields are part of the transcript covered by the MACs of the handshake, a version
+/// removed or changed on the way makes the handshake fail.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
+pub enum ProtocolVersion {
+    /// Keys derived from bare SHA-256 hashes of the nonces and the shared secret
+    V1,
+    /// Keys derived with HKDF-SHA256, bound to the transcript and the negotiated cipher
+    V2,
+}
+
+impl ProtocolVersion {
+    pub fn latest() -> ProtocolVersion {
+        ProtocolVersion::V2
+    }
+
+    pub fn number(self) -> u16 {
+        match self {
+            ProtocolVersion::V1 => 1,
+            ProtocolVersion::V2 => 2,
+        }
+    }
+
+    pub fn from_number(number: u16) -> Option<ProtocolVersion> {
+        match number {
+            1 => Some(ProtocolVersion::V1),
+            2 => Some(ProtocolVersion::V2),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::time::{Duration, Instant};
+
+use byteorder::{ByteOrder, LittleEndian};
+use hmac::{Hmac, Mac};
+use rand::{rngs::OsRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use cipher::Cipher;
+use key_schedule::Hkdf;
+use messages::{new_srd_rekey_msg, Message, SrdMessage};
+use srd::Srd;
+use srd_errors::SrdError;
+use srd_state::SrdState;
+use Result;
+
+const SEQUENCE_SIZE: usize = 8;
+const MAC_SIZE: usize = 32;
+
+/// Secure channel between the two sides of a completed handshake.
+///
+/// Each side seals with its own keys and opens with the keys of its peer, both derived from the handshake keys.
+/// A sealed record is its sequence number (u64, little endian) followed by the data encrypted with an AEAD
+/// cipher. Sequence numbers start at 0 in each direction and records must be opened in the order they were
+/// sealed: replayed, reordered or dropped records are rejected.
+///
+/// Each direction has a delegation key, from which records keys are derived, and an integrity key authenticating
+/// Rekey messages. `rekey` rotates both, see `needs_rekey` for the triggers set in the configuration.
+pub struct SrdSession {
+    cipher: Cipher,
+    write: DirectionKeys,
+    read: DirectionKeys,
+
+    rekey_after_bytes: Option<u64>,
+    rekey_after_messages: Option<u64>,
+    rekey_interval: Option<Duration>,
+    bytes_sealed: u64,
+    messages_sealed: u64,
+    // Only read with a rekey interval, some targets have no clock
+    rotated_at: Option<Instant>,
+}
+
+impl SrdSession {
+    /// Start a session from a completed handshake. The AEAD cipher is the negotiated one, or its authenticated
+    /// counterpart when an older cipher has been negotiated.
+    pub fn new(srd: &Srd) -> Result<SrdSession> {
+        if srd.state() != SrdState::Complete {
+            return Err(SrdError::BadSequence);
+        }
+
+        let (mut delegation_key, mut integrity_key) = srd._get_keys();
+        let hkdf = Hkdf::extract(&integrity_key, &delegation_key);
+        delegation_key.zeroize();
+        integrity_key.zeroize();
+
+        let client = DirectionKeys::derive(&hkdf, b"srd client");
+        let server = DirectionKeys::derive(&hkdf, b"srd server");
+        let (write, read) = if srd.is_server() {
+            (server, client)
+        } else {
+            (client, server)
+        };
+
+        let cipher = match srd.get_cipher() {
+            Cipher::AES256 => Cipher::AES256GCM,
+            Cipher::ChaCha20 => Cipher::ChaCha20Poly1305,
+            Cipher::XChaCha20 => Cipher::XChaCha20Poly1305,
+            cipher => cipher,
+        };
+
+        let config = srd.config();
+        Ok(SrdSession {
+            cipher,
+            write,
+            read,
+            rekey_after_bytes: config.rekey_after_bytes(),
+            rekey_after_messages: config.rekey_after_messages(),
+            rekey_interval: config.rekey_interval(),
+            bytes_sealed: 0,
+            messages_sealed: 0,
+            rotated_at: config.rekey_interval().map(|_| Instant::now()),
+        })
+    }
+
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    /// Bytes added to the data by `seal`.
+    pub fn overhead(&self) -> usize {
+        SEQUENCE_SIZE + self.cipher.tag_size()
+    }
+
+    /// Encrypt the next record sent to the peer.
+    pub fn seal(&mut self, data: &[u8]) -> Vec<u8> {
+        let sequence = self.write.next_sequence().expect("SRD session sequence numbers exhausted");
+
+        let mut nonce = self.write.nonce(sequence);
+        let encrypted_data = self
+            .cipher
+            .encrypt_data(data, &self.write.key, &nonce)
+            .expect("AEAD ciphers accept any data with a 32 bytes key");
+        nonce.zeroize();
+
+        self.bytes_sealed = self.bytes_sealed.saturating_add(data.len() as u64);
+        self.messages_sealed = self.messages_sealed.saturating_add(1);
+
+        let mut record = vec![0u8; SEQUENCE_SIZE];
+        LittleEndian::write_u64(&mut record, sequence);
+        record.extend_from_slice(&encrypted_data);
+        record
+    }
+
+    /// Decrypt the next record received from the peer. A record that fails doesn't change the expected sequence
+    /// number.
+    pub fn open(&mut self, record: &[u8]) -> Result<Vec<u8>> {
+        if record.len() < self.overhead() {
+            return Err(SrdError::InvalidDataLength);
+        }
+
+        // The sequence number is authenticated through the nonce
+        let sequence = LittleEndian::read_u64(&record[..SEQUENCE_SIZE]);
+        if sequence != self.read.sequence {
+            return Err(SrdError::BadSequence);
+        }
+
+        let mut nonce = self.read.nonce(sequence);
+        let data = self.cipher.decrypt_data(&record[SEQUENCE_SIZE..], &self.read.key, &nonce);
+        nonce.zeroize();
+
+        let data = data?;
+        self.read.next_sequence()?;
+        Ok(data)
+    }
+
+    /// Whether one of the rekey triggers of the configuration has been reached since the last rotation.
+    pub fn needs_rekey(&self) -> bool {
+        let reached = |limit: Option<u64>, count: u64| limit.map(|limit| count >= limit).unwrap_or(false);
+
+        reached(self.rekey_after_bytes, self.bytes_sealed)
+            || reached(self.rekey_after_messages, self.messages_sealed)
+            || match (self.rekey_interval, self.rotated_at) {
+                (Some(interval), Some(rotated_at)) => rotated_at.elapsed() >= interval,
+                _ => false,
+            }
+    }
+
+    /// Rotate the keys sealing records and return the Rekey message announcing it, to send before the next record.
+    /// The previous keys are erased.
+    pub fn rekey(&mut self) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; 32];
+        OsRng.try_fill_bytes(&mut nonce)?;
+
+        let mut message = new_srd_rekey_msg(self.write.sequence, nonce);
+        let mut buffer = Vec::new();
+        message.write_to(&mut buffer)?;
+        let mac = self.write.hmac(&buffer[..buffer.len() - MAC_SIZE])?.result().code();
+        message.set_mac(&mac)?;
+
+        buffer.clear();
+        message.write_to(&mut buffer)?;
+
+        self.write.rotate(&nonce);
+        self.bytes_sealed = 0;
+        self.messages_sealed = 0;
+        if self.rotated_at.is_some() {
+            self.rotated_at = Some(Instant::now());
+        }
+        Ok(buffer)
+    }
+
+    /// Handle a Rekey message from the peer. It must be received between the records sealed before and after it.
+    pub fn receive_rekey(&mut self, buffer: &[u8]) -> Result<()> {
+        let mut reader = std::io::Cursor::new(buffer);
+        let message = SrdMessage::read_from(&mut reader)?;
+        if reader.position() as usize != buffer.len() {
+            return Err(SrdError::InvalidDataLength);
+        }
+
+        match message {
+            SrdMessage::Rekey(_, ref rekey) => {
+                self.read
+                    .hmac(&buffer[..buffer.len() - MAC_SIZE])?
+                    .verify(rekey.mac())
+                    .map_err(|_| SrdError::InvalidMac)?;
+
+                if rekey.sequence != self.read.sequence {
+                    return Err(SrdError::BadSequence);
+                }
+
+                self.read.rotate(&rekey.nonce);
+                Ok(())
+            }
+            _ => Err(SrdError::UnknownMsgType),
+        }
+    }
+}
+
+// Keys of one direction of a session
+struct DirectionKeys {
+    delegation_key: [u8; 32],
+    integrity_key: [u8; 32],
+    key: [u8; 32],
+    iv: [u8; 32],
+    sequence: u64,
+}
+
+impl DirectionKeys {
+    fn derive(hkdf: &Hkdf, direction: &[u8]) -> DirectionKeys {
+        let mut keys = DirectionKeys {
+            delegation_key: [0; 32],
+            integrity_key: [0; 32],
+            key: [0; 32],
+            iv: [0; 32],
+            sequence: 0,
+        };
+        hkdf.expand(&[direction, b" delegation key"], &mut keys.delegation_key);
+        hkdf.expand(&[direction, b" integrity key"], &mut keys.integrity_key);
+        keys.derive_record_keys();
+        keys
+    }
+
+    fn derive_record_keys(&mut self) {
+        let hkdf = Hkdf::from_prk(&self.delegation_key);
+        hkdf.expand(&[b"srd record key"], &mut self.key);
+        hkdf.expand(&[b"srd record iv"], &mut self.iv);
+    }
+
+    // Next generation of keys, from the current ones and the nonce of the Rekey message
+    fn rotate(&mut self, nonce: &[u8; 32]) {
+        let mut current = [0u8; 64];
+        current[..32].copy_from_slice(&self.delegation_key);
+        current[32..].copy_from_slice(&self.integrity_key);
+        let hkdf = Hkdf::extract(nonce, &current);
+        current.zeroize();
+
+        self.delegation_key.zeroize();
+        self.integrity_key.zeroize();
+        hkdf.expand(&[b"srd rekey delegation key"], &mut self.delegation_key);
+        hkdf.expand(&[b"srd rekey integrity key"], &mut self.integrity_key);
+        self.derive_record_keys();
+    }
+
+    fn next_sequence(&mut self) -> Result<u64> {
+        let sequence = self.sequence;
+        self.sequence = sequence.checked_add(1).ok_or(SrdError::BadSequence)?;
+        Ok(sequence)
+    }
+
+    // The sequence number goes in the first 12 bytes of the iv, the part used by every cipher
+    fn nonce(&self, sequence: u64) -> [u8; 32] {
+        let mut nonce = self.iv;
+        let mut sequence_bytes = [0u8; SEQUENCE_SIZE];
+        LittleEndian::write_u64(&mut sequence_bytes, sequence);
+        for (byte, sequence_byte) in nonce[4..12].iter_mut().zip(sequence_bytes.iter()) {
+            *byte ^= sequence_byte;
+        }
+        nonce
+    }
+
+    // Rekey messages are authenticated on their own, without the MAC field
+    fn hmac(&self, message: &[u8]) -> Result<Hmac<Sha256>> {
+        let mut hmac = Hmac::<Sha256>::new_varkey(&self.integrity_key)?;
+        hmac.input(message);
+        Ok(hmac)
+    }
+}
+
+impl Drop for DirectionKeys {
+    fn drop(&mut self) {
+        self.delegation_key.zeroize();
+        self.integrity_key.zeroize();
+        self.key.zeroize();
+        self.iv.zeroize();
+    }
+}
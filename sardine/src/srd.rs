@@ -1,5 +1,6 @@
 use std;
 use std::io::Write;
+use std::sync::Arc;
 
 use rand::{rngs::OsRng, RngCore};
 
@@ -7,14 +8,24 @@ use num_bigint::BigUint;
 
 use hmac::{Hmac, Mac};
 use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
 
 use cipher::Cipher;
 use Result;
 
+use credentials::{BlobProvider, CredentialValidator, DelegationContext};
+
 use blobs::{Blob, SrdBlob};
-use dh_params::SRD_DH_PARAMS;
+use dh_group::{exponent_size, DhGroup};
+use dh_validation::{validate_dh_params, validate_public_key};
+use key_exchange::{dh_key_pair, x25519_key_pair, x25519_shared_secret, KeyExchange};
+use key_schedule::{export_keying_material, Hkdf};
 use messages::*;
+use modexp::{generator_table, modpow};
+use protocol_version::ProtocolVersion;
+use srd_config::SrdConfig;
 use srd_errors::SrdError;
+use srd_state::{SrdState, SrdStep};
 
 cfg_if! {
     if #[cfg(feature = "wasm")] {
@@ -45,31 +56,16 @@ cfg_if! {
             }
 
             pub fn authenticate(&mut self, input_data: &[u8]) -> SrdJsResult {
-                let mut output_data = Vec::new();
-                self._authenticate(&input_data, &mut output_data).unwrap();
-                SrdJsResult {
-                    output_data,
-                    res_code: -1,
-                }
-                /*match self._authenticate(&input_data, &mut output_data) {
+                match self.step(input_data) {
                     Err(_) => SrdJsResult {
-                        output_data,
+                        output_data: self.alert.clone().unwrap_or_default(),
                         res_code: -1,
                     },
-                    Ok(b) => {
-                        if b {
-                            SrdJsResult {
-                                output_data,
-                                res_code: 0,
-                            }
-                        } else {
-                            SrdJsResult {
-                                output_data,
-                                res_code: 1,
-                            }
-                        }
-                    }
-                }*/
+                    Ok(step) => SrdJsResult {
+                        output_data: step.output_data().map(|data| data.to_vec()).unwrap_or_default(),
+                        res_code: if step.is_complete() { 0 } else { 1 },
+                    },
+                }
             }
 
             pub fn get_delegation_key(&self) -> Vec<u8> {
@@ -87,9 +83,51 @@ cfg_if! {
             pub fn set_cert_data(&mut self, buffer: Vec<u8>) {
                 self._set_cert_data(buffer).unwrap();
             }
+
+            pub fn set_delegation_result(&mut self, success: bool, payload: &[u8]) -> SrdJsResult {
+                let result = if success {
+                    DelegationResult::success(payload.to_vec())
+                } else {
+                    DelegationResult::failure(payload.to_vec())
+                };
+                match self._set_delegation_result(result) {
+                    Ok(output_data) => SrdJsResult {
+                        output_data,
+                        res_code: 0,
+                    },
+                    Err(_) => SrdJsResult {
+                        output_data: Vec::new(),
+                        res_code: -1,
+                    },
+                }
+            }
+
+            pub fn delegation_succeeded(&self) -> Option<bool> {
+                self.delegation_result.as_ref().map(|result| result.is_success())
+            }
+
+            pub fn delegation_result_payload(&self) -> Vec<u8> {
+                self.delegation_result.as_ref().map(|result| result.payload().to_vec()).unwrap_or_default()
+            }
+
+            pub fn export_keying_material(&self, label: &[u8], context: &[u8], length: usize) -> SrdJsResult {
+                match self._export_keying_material(label, context, length) {
+                    Ok(output_data) => SrdJsResult {
+                        output_data,
+                        res_code: 0,
+                    },
+                    Err(_) => SrdJsResult {
+                        output_data: Vec::new(),
+                        res_code: -1,
+                    },
+                }
+            }
         }
     }
     else {
+        use ephemeral_pool::EphemeralKeyPool;
+        use session::SrdSession;
+
         // Native public functions
         #[cfg(not(feature = "wasm"))]
         impl Srd {
@@ -97,12 +135,21 @@ cfg_if! {
                 Srd::_new(is_server, skip_delegation)
             }
 
+            /// On failure, `output_data` receives the alert for the peer if the configuration sends them.
             pub fn authenticate(&mut self, input_data: &[u8], output_data: &mut Vec<u8>) -> Result<bool> {
-                self._authenticate(&input_data, output_data)
+                let step = self.step(input_data).inspect_err(|_| {
+                    if let Some(ref alert) = self.alert {
+                        output_data.extend_from_slice(alert);
+                    }
+                })?;
+                if let Some(data) = step.output_data() {
+                    output_data.extend_from_slice(data);
+                }
+                Ok(step.is_complete())
             }
 
             pub fn get_keys(&self) -> ([u8; 32], [u8; 32]) {
-                (self.delegation_key, self.integrity_key)
+                self._get_keys()
             }
 
             pub fn get_delegation_key(&self) -> Vec<u8> {
@@ -122,6 +169,26 @@ cfg_if! {
                 Ok(())
             }
 
+            /// Derive `length` bytes of keying material for an upper-layer protocol, as RFC 5705 does for TLS.
+            /// Both sides get the same bytes for the same label and context, unrelated to the keys of the handshake.
+            /// Only available once the handshake is complete.
+            pub fn export_keying_material(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>> {
+                self._export_keying_material(label, context, length)
+            }
+
+            /// Tell the client whether the delegated blob was accepted, once a server has handled the Delegate
+            /// message of a handshake with a delegation result. Returns the Result message to send, which completes
+            /// the handshake.
+            pub fn set_delegation_result(&mut self, result: DelegationResult) -> Result<Vec<u8>> {
+                self._set_delegation_result(result)
+            }
+
+            /// Take the server key pairs from a pool filled in the background rather than generating them during
+            /// the handshake.
+            pub fn set_key_pool(&mut self, key_pool: Arc<EphemeralKeyPool>) {
+                self.key_pool = Some(key_pool);
+            }
+
             pub fn get_output_data(&self) -> &Option<Vec<u8>> {
                 &self.output_data
             }
@@ -129,6 +196,14 @@ cfg_if! {
             pub fn set_output_data(&mut self, output_data: Vec<u8>) {
                 self.output_data = Some(output_data);
             }
+
+            /// Session kept along the handshake for the FFI, started on first use.
+            pub(crate) fn session_mut(&mut self) -> Result<&mut SrdSession> {
+                if self.session.is_none() {
+                    self.session = Some(SrdSession::new(self)?);
+                }
+                Ok(self.session.as_mut().expect("session has just been started"))
+            }
         }
 
     }
@@ -138,33 +213,56 @@ cfg_if! {
 #[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
 pub struct Srd {
     blob: Option<SrdBlob>,
+    delegation_result: Option<DelegationResult>,
     output_data: Option<Vec<u8>>,
+    alert: Option<Vec<u8>>,
+    peer_alert_text: Option<String>,
+
+    config: Arc<SrdConfig>,
 
     is_server: bool,
-    skip_delegation: bool,
     key_size: u16,
     seq_num: u8,
-    state: u8,
+    state: SrdState,
+    #[cfg_attr(feature = "ser", serde(skip))]
+    failure: Option<SrdError>,
 
     messages: Vec<Vec<u8>>,
 
-    cert_data: Option<Vec<u8>>,
     use_cbt: bool,
+    use_result: bool,
+    use_blob_types: bool,
+    accepted_blob_types: Vec<String>,
 
     client_nonce: [u8; 32],
     server_nonce: [u8; 32],
     delegation_key: [u8; 32],
     integrity_key: [u8; 32],
     iv: [u8; 32],
+    exporter_secret: [u8; 32],
 
-    supported_ciphers: Vec<Cipher>,
     cipher: Cipher,
+    key_exchange: KeyExchange,
+    dh_group: Option<DhGroup>,
+    version: ProtocolVersion,
 
     generator: BigUint,
 
     prime: BigUint,
-    private_key: BigUint,
+    private_key: Vec<u8>,
+    x25519_private_key: [u8; 32],
     secret_key: Vec<u8>,
+
+    #[cfg_attr(feature = "ser", serde(skip))]
+    credential_validator: Option<Arc<dyn CredentialValidator>>,
+    #[cfg_attr(feature = "ser", serde(skip))]
+    blob_provider: Option<Arc<dyn BlobProvider>>,
+    #[cfg(not(feature = "wasm"))]
+    #[cfg_attr(feature = "ser", serde(skip))]
+    key_pool: Option<Arc<EphemeralKeyPool>>,
+    #[cfg(not(feature = "wasm"))]
+    #[cfg_attr(feature = "ser", serde(skip))]
+    session: Option<SrdSession>,
 }
 
 // Same implementation, both public
@@ -176,120 +274,286 @@ impl Srd {
 }
 
 impl Srd {
-    fn _new(is_server: bool, skip_delegation: bool) -> Srd {
-        let supported_ciphers;
-        if cfg!(feature = "fips") {
-            supported_ciphers = vec![Cipher::AES256];
-        } else if cfg!(feature = "aes") {
-            supported_ciphers = vec![Cipher::XChaCha20, Cipher::ChaCha20, Cipher::AES256];
-        } else {
-            supported_ciphers = vec![Cipher::XChaCha20, Cipher::ChaCha20];
-        }
+    pub(crate) fn _new(is_server: bool, skip_delegation: bool) -> Srd {
+        let mut config = SrdConfig::default();
+        config.set_skip_delegation(skip_delegation);
+        Srd::with_config(is_server, Arc::new(config))
+    }
 
+    /// Create a handshake sharing a configuration built once with `SrdConfig::builder`.
+    pub fn with_config(is_server: bool, config: Arc<SrdConfig>) -> Srd {
         Srd {
             blob: None,
+            delegation_result: None,
             output_data: None,
+            alert: None,
+            peer_alert_text: None,
 
             is_server,
-            skip_delegation,
-            key_size: 256,
+            key_size: config.key_size(),
             seq_num: 0,
-            state: 0,
+            state: if is_server {
+                SrdState::AwaitingInitiate
+            } else {
+                SrdState::Initial
+            },
+            failure: None,
 
             messages: Vec::new(),
 
-            cert_data: None,
-            use_cbt: false,
+            use_cbt: config.cert_data().is_some(),
+            use_result: false,
+            use_blob_types: false,
+            accepted_blob_types: Vec::new(),
 
             client_nonce: [0; 32],
             server_nonce: [0; 32],
             delegation_key: [0; 32],
             integrity_key: [0; 32],
             iv: [0; 32],
+            exporter_secret: [0; 32],
 
-            supported_ciphers,
             cipher: Cipher::XChaCha20,
+            key_exchange: KeyExchange::FiniteField,
+            dh_group: None,
+            version: ProtocolVersion::V1,
 
             generator: BigUint::from_bytes_be(&[0]),
 
             prime: BigUint::from_bytes_be(&[0]),
-            private_key: BigUint::from_bytes_be(&[0]),
+            private_key: Vec::new(),
+            x25519_private_key: [0; 32],
             secret_key: Vec::new(),
+
+            credential_validator: None,
+            blob_provider: None,
+            #[cfg(not(feature = "wasm"))]
+            key_pool: None,
+            #[cfg(not(feature = "wasm"))]
+            session: None,
+
+            config,
         }
     }
 
-    fn _authenticate(&mut self, input_data: &[u8], output_data: &mut Vec<u8>) -> Result<bool> {
+    pub fn step(&mut self, input_data: &[u8]) -> Result<SrdStep> {
         // We don't want anybody to access previous output_data.
         self.output_data = None;
 
-        if self.is_server {
-            match self.state {
-                0 => self.server_authenticate_0(input_data, output_data)?,
-                1 => {
-                    self.server_authenticate_1(input_data, output_data)?;
-                    if self.skip_delegation {
-                        self.state += 1;
-                        return Ok(true);
-                    }
+        match self.state {
+            // A failed handshake keeps reporting the error that stopped it
+            SrdState::Failed(_) => {
+                return Err(self
+                    .failure
+                    .clone()
+                    .unwrap_or_else(|| SrdError::Internal("Failed handshake without an error".to_owned())));
+            }
+            // Nothing left to do, but a completed handshake must not be invalidated by an extra call
+            SrdState::Complete => return Err(SrdError::BadSequence),
+            _ => {}
+        }
+
+        let mut output_data = Vec::new();
+        match self.authenticate_step(input_data, &mut output_data) {
+            Ok(state) => {
+                self.state = state;
+                Ok(match (state == SrdState::Complete, output_data.is_empty()) {
+                    (false, false) => SrdStep::Send(output_data),
+                    (false, true) => SrdStep::Continue,
+                    (true, false) => SrdStep::SendAndComplete(output_data),
+                    (true, true) => SrdStep::Complete {
+                        blob: if self.is_server { self.blob.clone() } else { None },
+                    },
+                })
+            }
+            Err(error) => {
+                // A refused delegation may already have its Result message for the peer
+                if self.alert.is_none() && self.config.send_alerts() {
+                    self.alert = self.alert_msg(&error);
                 }
-                2 => {
-                    self.server_authenticate_2(input_data)?;
-                    self.state += 1;
-                    return Ok(true);
+                self.state = SrdState::Failed(error.kind());
+                self.failure = Some(error.clone());
+                Err(error)
+            }
+        }
+    }
+
+    // The alert is not sent back to a peer whose own alert stopped the handshake
+    fn alert_msg(&self, error: &SrdError) -> Option<Vec<u8>> {
+        if let SrdError::PeerAlert(_) = *error {
+            return None;
+        }
+
+        // Alerts aren't authenticated, the peer only learns the code of the error
+        let code = AlertCode::from_error(error);
+        let msg = new_srd_alert_msg(self.seq_num, code, Some(code.text().to_owned()));
+        let mut buffer = Vec::new();
+        msg.write_to(&mut buffer).ok()?;
+        Some(buffer)
+    }
+
+    // Process one message and return the state reached once it has been handled.
+    fn authenticate_step(&mut self, input_data: &[u8], output_data: &mut Vec<u8>) -> Result<SrdState> {
+        match self.state {
+            SrdState::Initial if !self.is_server => {
+                self.client_authenticate_0(output_data)?;
+                Ok(SrdState::AwaitingOffer)
+            }
+            SrdState::AwaitingInitiate if self.is_server => {
+                self.server_authenticate_0(input_data, output_data)?;
+                Ok(SrdState::AwaitingAccept)
+            }
+            SrdState::AwaitingOffer if !self.is_server => {
+                self.client_authenticate_1(input_data, output_data)?;
+                Ok(SrdState::AwaitingConfirm)
+            }
+            SrdState::AwaitingAccept if self.is_server => {
+                self.server_authenticate_1(input_data, output_data)?;
+                if self.config.skip_delegation() {
+                    Ok(SrdState::Complete)
+                } else {
+                    Ok(SrdState::AwaitingDelegate)
                 }
-                _ => return Err(SrdError::BadSequence),
             }
-        } else {
-            match self.state {
-                0 => self.client_authenticate_0(output_data)?,
-                1 => self.client_authenticate_1(input_data, output_data)?,
-                2 => {
-                    self.client_authenticate_2(input_data, output_data)?;
-                    self.state += 1;
-                    return Ok(true);
+            SrdState::AwaitingConfirm if !self.is_server => {
+                self.client_authenticate_2(input_data, output_data)?;
+                if self.use_result {
+                    Ok(SrdState::AwaitingResult)
+                } else {
+                    Ok(SrdState::Complete)
+                }
+            }
+            SrdState::AwaitingDelegate if self.is_server => {
+                self.server_authenticate_2(input_data)?;
+                if let Some(validator) = self.credential_validator.clone() {
+                    let blob = self.blob.as_ref().ok_or(SrdError::MissingBlob)?;
+                    let result = validator.validate(blob)?;
+                    return self.apply_delegation_result(result, output_data);
+                }
+                if self.use_result {
+                    Ok(SrdState::AwaitingDelegationResult)
+                } else {
+                    Ok(SrdState::Complete)
                 }
-                _ => return Err(SrdError::BadSequence),
             }
+            SrdState::AwaitingResult if !self.is_server => {
+                self.client_authenticate_3(input_data)?;
+                Ok(SrdState::Complete)
+            }
+            _ => Err(SrdError::BadSequence),
         }
-        self.state += 1;
-        Ok(false)
     }
 
-    fn _set_cert_data(&mut self, buffer: Vec<u8>) -> Result<()> {
-        self.cert_data = Some(buffer);
-        self.use_cbt = true;
-        Ok(())
+    pub fn state(&self) -> SrdState {
+        self.state
     }
 
-    pub fn set_ciphers(&mut self, ciphers: Vec<Cipher>) -> Result<()> {
-        if cfg!(feature = "fips") {
-            return Err(SrdError::Cipher);
+    pub(crate) fn _set_delegation_result(&mut self, result: DelegationResult) -> Result<Vec<u8>> {
+        if self.state != SrdState::AwaitingDelegationResult {
+            return Err(SrdError::BadSequence);
         }
 
-        if cfg!(not(feature = "aes")) {
-            if ciphers.contains(&Cipher::AES256) {
-                return Err(SrdError::Cipher);
-            }
+        let mut output_data = Vec::new();
+        self.write_result_msg(&result, &mut output_data)?;
+
+        self.delegation_result = Some(result);
+        self.state = SrdState::Complete;
+        Ok(output_data)
+    }
+
+    // Verdict of the credential validator. With a Result message, the verdict is sent and the handshake completes
+    // whether the blob was accepted or not, as with `set_delegation_result`. Otherwise a refused blob fails it.
+    fn apply_delegation_result(&mut self, result: DelegationResult, output_data: &mut Vec<u8>) -> Result<SrdState> {
+        if self.use_result {
+            self.write_result_msg(&result, output_data)?;
+            self.delegation_result = Some(result);
+            return Ok(SrdState::Complete);
         }
 
-        self.supported_ciphers = ciphers;
+        let success = result.is_success();
+        self.delegation_result = Some(result);
+        if success {
+            Ok(SrdState::Complete)
+        } else {
+            Err(SrdError::InvalidCredentials)
+        }
+    }
+
+    fn write_result_msg(&mut self, result: &DelegationResult, output_data: &mut Vec<u8>) -> Result<()> {
+        let mut out_msg = new_srd_result_msg(self.seq_num, self.use_cbt, result);
+        self.write_msg(&mut out_msg, output_data)
+    }
+
+    /// Check the blob delegated by the client before the handshake completes, see `CredentialValidator`.
+    pub fn set_credential_validator(&mut self, validator: Arc<dyn CredentialValidator>) {
+        self.credential_validator = Some(validator);
+    }
+
+    /// Build the delegated blob only once the server is authenticated, see `BlobProvider`. It replaces any blob
+    /// set beforehand.
+    pub fn set_blob_provider(&mut self, provider: Arc<dyn BlobProvider>) {
+        self.blob_provider = Some(provider);
+    }
+
+    /// Whether the server accepted the delegated blob, once the Result message has been sent or received.
+    pub fn get_delegation_result(&self) -> Option<&DelegationResult> {
+        self.delegation_result.as_ref()
+    }
+
+    pub(crate) fn is_server(&self) -> bool {
+        self.is_server
+    }
+
+    pub(crate) fn _export_keying_material(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>> {
+        if self.state != SrdState::Complete {
+            return Err(SrdError::BadSequence);
+        }
+        export_keying_material(&self.exporter_secret, label, context, length)
+    }
+
+    pub(crate) fn exporter_secret(&self) -> [u8; 32] {
+        self.exporter_secret
+    }
+
+    /// Alert telling the peer why the handshake failed, when the configuration sends them.
+    pub fn get_alert(&self) -> Option<&[u8]> {
+        self.alert.as_deref()
+    }
+
+    /// Text of the alert that stopped the handshake with `SrdError::PeerAlert`, if the peer sent one.
+    pub fn get_peer_alert_text(&self) -> Option<&str> {
+        self.peer_alert_text.as_deref()
+    }
+
+    pub fn expected_next_message(&self) -> Option<SrdMessageType> {
+        self.state.expected_next_message()
+    }
+
+    pub(crate) fn _set_cert_data(&mut self, buffer: Vec<u8>) -> Result<()> {
+        Arc::make_mut(&mut self.config).set_cert_data(buffer);
+        self.use_cbt = true;
         Ok(())
     }
 
+    pub fn config(&self) -> &SrdConfig {
+        &self.config
+    }
+
+    /// Set the allowed ciphers, most preferred first.
+    pub fn set_ciphers(&mut self, ciphers: Vec<Cipher>) -> Result<()> {
+        Arc::make_mut(&mut self.config).set_ciphers(ciphers)
+    }
+
     pub fn get_blob<T: Blob>(&self) -> Result<Option<T>> {
-        if self.blob.is_some() {
-            let blob = self.blob.as_ref().unwrap();
-            if blob.blob_type() == T::blob_type() {
-                let mut cursor = std::io::Cursor::new(blob.data());
-                return Ok(Some(T::read_from(&mut cursor)?));
-            }
+        match self.blob {
+            Some(ref blob) => blob.get(),
+            None => Ok(None),
         }
-        Ok(None)
     }
 
     pub fn set_blob<T: Blob>(&mut self, blob: T) -> Result<()> {
-        let mut data = Vec::new();
-        blob.write_to(&mut data)?;
+        let mut data = Zeroizing::new(Vec::new());
+        blob.write_to(&mut *data)?;
         self.blob = Some(SrdBlob::new(T::blob_type(), &data));
         Ok(())
     }
@@ -298,7 +562,34 @@ impl Srd {
         return self.blob.clone();
     }
 
+    pub(crate) fn _get_keys(&self) -> ([u8; 32], [u8; 32]) {
+        (self.delegation_key, self.integrity_key)
+    }
+
+    pub fn get_key_exchange(&self) -> KeyExchange {
+        self.key_exchange
+    }
+
+    /// Finite-field group of the handshake, if it is a known one.
+    pub fn get_dh_group(&self) -> Option<DhGroup> {
+        self.dh_group
+    }
+
+    /// Protocol version negotiated with the peer, version 1 until the offer is handled.
+    pub fn get_version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    // Without a delegation, there is nothing to send a result about
+    fn wants_result(&self) -> bool {
+        self.config.delegation_result() && !self.config.skip_delegation()
+    }
+
     fn set_key_size(&mut self, key_size: u16) -> Result<()> {
+        if key_size < self.config.key_size() {
+            return Err(SrdError::InvalidKeySize);
+        }
+
         match key_size {
             256 | 512 | 1024 => {
                 self.key_size = key_size;
@@ -309,19 +600,32 @@ impl Srd {
     }
 
     fn read_msg(&mut self, buffer: &[u8]) -> Result<SrdMessage> {
+        if buffer.len() > self.config.max_message_size() {
+            return Err(SrdError::InvalidDataLength);
+        }
+
         let mut reader = std::io::Cursor::new(buffer);
         let msg = SrdMessage::read_from(&mut reader)?;
+        if reader.position() as usize != buffer.len() {
+            return Err(SrdError::InvalidDataLength);
+        }
+
+        // The peer gave up, whatever message was expected
+        if let SrdMessage::Alert(_, ref alert) = msg {
+            self.peer_alert_text = alert.text().map(|text| text.to_owned());
+            return Err(SrdError::PeerAlert(alert.code()));
+        }
 
         if msg.seq_num() != self.seq_num {
             return Err(SrdError::BadSequence);
         }
         self.seq_num += 1;
 
-        if msg.has_skip() && !self.skip_delegation {
+        if msg.has_skip() && !self.config.skip_delegation() {
             return Err(SrdError::Proto(String::from("SRD_FLAG_SKIP not expected")));
         }
 
-        if !msg.has_skip() && self.skip_delegation {
+        if !msg.has_skip() && self.config.skip_delegation() {
             return Err(SrdError::Proto(String::from("SRD_FLAG_SKIP expected")));
         }
 
@@ -351,7 +655,7 @@ impl Srd {
             return Err(SrdError::BadSequence);
         }
 
-        if self.skip_delegation {
+        if self.config.skip_delegation() {
             msg.set_skip();
         }
 
@@ -383,8 +687,8 @@ impl Srd {
             let mut hmac = Hmac::<Sha256>::new_varkey(&self.integrity_key)?;
 
             hmac.input(nonce);
-            if let Some(ref cert_data) = self.cert_data {
-                hmac.input(&cert_data);
+            if let Some(cert_data) = self.config.cert_data() {
+                hmac.input(cert_data);
             } else {
                 return Err(SrdError::InvalidCert);
             }
@@ -447,7 +751,7 @@ impl Srd {
     // Client initiate
     fn client_authenticate_0(&mut self, mut output_data: &mut Vec<u8>) -> Result<()> {
         let mut cipher_flags = 0u32;
-        for c in &self.supported_ciphers {
+        for c in self.config.ciphers() {
             cipher_flags |= c.flag();
         }
 
@@ -455,8 +759,34 @@ impl Srd {
             return Err(SrdError::Cipher);
         }
 
+        // Finite-field Diffie-Hellman with the legacy groups is what older clients do without advertising it, the
+        // Initiate message is then the one they send
+        let mut key_exchange_flags = 0u16;
+        if self.config.key_exchanges() != [KeyExchange::FiniteField] || !self.config.dh_group().is_legacy() {
+            for k in self.config.key_exchanges() {
+                key_exchange_flags |= k.flag();
+            }
+        }
+
         // Negotiate
-        let mut out_msg = new_srd_initiate_msg(self.seq_num, self.use_cbt, cipher_flags, self.key_size)?;
+        let mut out_msg = new_srd_initiate_msg(
+            self.seq_num,
+            self.use_cbt,
+            cipher_flags,
+            self.key_size,
+            key_exchange_flags,
+        )?;
+
+        // Version 1 servers would reject a version they don't know about
+        if self.config.max_version() > ProtocolVersion::V1 {
+            out_msg.set_version(self.config.max_version().number());
+            if !self.config.skip_delegation() {
+                out_msg.set_blob_types(&[]);
+            }
+        }
+        if self.wants_result() {
+            out_msg.set_result();
+        }
         self.write_msg(&mut out_msg, &mut output_data)?;
         Ok(())
     }
@@ -469,22 +799,32 @@ impl Srd {
             SrdMessage::Initiate(hdr, initiate) => {
                 self.use_cbt = hdr.has_cbt();
 
-                // Negotiate
-                self.set_key_size(initiate.key_size())?;
-                self.find_dh_parameters()?;
-
-                let key_size = initiate.key_size();
+                // Negotiate. Take our most preferred key exchange that the client supports.
+                let client_key_exchanges = KeyExchange::from_flags(initiate.key_exchanges());
+                self.key_exchange = *self
+                    .config
+                    .key_exchanges()
+                    .iter()
+                    .find(|k| client_key_exchanges.contains(k))
+                    .ok_or(SrdError::KeyExchange)?;
+
+                // Take the highest version both sides support. Clients that don't advertise one use version 1.
+                if let Some(client_version) = initiate.version {
+                    let version = std::cmp::min(client_version, self.config.max_version().number());
+                    self.version = ProtocolVersion::from_number(version).ok_or(SrdError::UnsupportedVersion)?;
+                }
+                if self.version < self.config.min_version() {
+                    return Err(SrdError::UnsupportedVersion);
+                }
 
-                let mut private_key_bytes = vec![0u8; self.key_size as usize];
-                OsRng.try_fill_bytes(&mut private_key_bytes)?;
+                self.use_result = hdr.has_result() && self.wants_result();
+                // Older clients wouldn't read the blob types, they are still refused other blobs
+                self.use_blob_types = hdr.has_blob_types() && !self.config.accepted_blob_types().is_empty();
 
-                // Challenge
-                self.private_key = BigUint::from_bytes_be(&private_key_bytes);
-                let public_key = self.generator.modpow(&self.private_key, &self.prime);
                 OsRng.try_fill_bytes(&mut self.server_nonce)?;
 
                 let mut cipher_flags = 0u32;
-                for c in &self.supported_ciphers {
+                for c in self.config.ciphers() {
                     cipher_flags |= c.flag();
                 }
 
@@ -492,16 +832,68 @@ impl Srd {
                     return Err(SrdError::Cipher);
                 }
 
-                let mut out_msg = new_srd_offer_msg(
-                    self.seq_num,
-                    self.use_cbt,
-                    cipher_flags,
-                    key_size,
-                    self.generator.to_bytes_be(),
-                    self.prime.to_bytes_be(),
-                    public_key.to_bytes_be(),
-                    self.server_nonce,
-                );
+                // Challenge
+                let mut out_msg = match self.key_exchange {
+                    KeyExchange::FiniteField => {
+                        // Clients that don't advertise key exchanges can't identify a group. They get the legacy
+                        // group of the size they ask for.
+                        let legacy_client = initiate.key_exchanges() == 0;
+                        self.set_key_size(initiate.key_size())?;
+                        let group = if legacy_client {
+                            DhGroup::legacy(self.key_size).ok_or(SrdError::InvalidKeySize)?
+                        } else {
+                            self.config.dh_group()
+                        };
+
+                        if group.key_size() < initiate.key_size() {
+                            return Err(SrdError::InvalidKeySize);
+                        }
+
+                        self.key_size = group.key_size();
+                        self.dh_group = Some(group);
+                        self.generator = BigUint::from_bytes_be(group.generator());
+                        self.prime = BigUint::from_bytes_be(group.prime());
+
+                        let (private_key, public_key) = match self.pooled_key_pair(group) {
+                            Some(key_pair) => key_pair,
+                            None => dh_key_pair(group)?,
+                        };
+                        self.private_key = private_key;
+
+                        if legacy_client {
+                            new_srd_offer_msg(
+                                self.seq_num,
+                                self.use_cbt,
+                                cipher_flags,
+                                group,
+                                public_key,
+                                self.server_nonce,
+                            )
+                        } else {
+                            new_srd_dh_group_offer_msg(
+                                self.seq_num,
+                                self.use_cbt,
+                                cipher_flags,
+                                group,
+                                public_key,
+                                self.server_nonce,
+                            )
+                        }
+                    }
+                    KeyExchange::X25519 => {
+                        let (private_key, public_key) = x25519_key_pair()?;
+                        self.x25519_private_key = private_key;
+
+                        new_srd_x25519_offer_msg(self.seq_num, self.use_cbt, cipher_flags, public_key, self.server_nonce)
+                    }
+                };
+
+                if initiate.version.is_some() {
+                    out_msg.set_version(self.version.number());
+                }
+                if self.use_result {
+                    out_msg.set_result();
+                }
 
                 self.write_msg(&mut out_msg, &mut output_data)?;
 
@@ -518,33 +910,101 @@ impl Srd {
         //Challenge
         let input_msg = self.read_msg(input_data)?;
         match input_msg {
-            SrdMessage::Offer(_hdr, offer) => {
-                // Verify server key_size
-                if offer.key_size() != self.key_size {
-                    return Err(SrdError::Proto(
-                        "Key size received in offer message is not equal to key size sent to server".to_owned(),
-                    ));
+            SrdMessage::Offer(hdr, offer) => {
+                self.key_exchange = if hdr.has_x25519() {
+                    KeyExchange::X25519
+                } else {
+                    KeyExchange::FiniteField
+                };
+
+                if !self.config.key_exchanges().contains(&self.key_exchange) {
+                    return Err(SrdError::KeyExchange);
                 }
 
+                // The server can't select a version higher than the one we advertised
+                self.version = match offer.version {
+                    Some(version) => ProtocolVersion::from_number(version)
+                        .filter(|version| *version <= self.config.max_version())
+                        .ok_or_else(|| SrdError::Proto(format!("Unexpected protocol version {}", version)))?,
+                    None => ProtocolVersion::V1,
+                };
+                if self.version < self.config.min_version() {
+                    return Err(SrdError::UnsupportedVersion);
+                }
+
+                // The server only sends a result when asked to
+                if hdr.has_result() && !self.wants_result() {
+                    return Err(SrdError::Proto("Unexpected delegation result".to_owned()));
+                }
+                self.use_result = hdr.has_result();
+
+                // Take our most preferred cipher that the server supports. Keys of recent versions depend on it.
                 let server_ciphers = Cipher::from_flags(offer.ciphers);
+                self.cipher = *self
+                    .config
+                    .ciphers()
+                    .iter()
+                    .find(|c| server_ciphers.contains(c))
+                    .ok_or(SrdError::Cipher)?;
+
+                let public_key = match self.key_exchange {
+                    KeyExchange::FiniteField => {
+                        // Verify server key_size. A server identifying its group may use a larger one than requested.
+                        match offer.group_id {
+                            Some(_) => {
+                                if offer.key_size() < self.key_size {
+                                    return Err(SrdError::InvalidKeySize);
+                                }
+                            }
+                            None => {
+                                if offer.key_size() != self.key_size {
+                                    return Err(SrdError::Proto(
+                                        "Key size received in offer message is not equal to key size sent to server"
+                                            .to_owned(),
+                                    ));
+                                }
+                            }
+                        }
+                        self.key_size = offer.key_size();
 
-                self.generator = BigUint::from_bytes_be(&offer.generator);
-                self.prime = BigUint::from_bytes_be(&offer.prime);
+                        self.generator = BigUint::from_bytes_be(&offer.generator);
+                        self.prime = BigUint::from_bytes_be(&offer.prime);
 
-                let mut private_key_bytes = vec![0u8; self.key_size as usize];
+                        // Don't trust the server with the group, nor with its public key
+                        self.dh_group = validate_dh_params(offer.group_id, &self.generator, &self.prime)?;
+                        let server_public_key = BigUint::from_bytes_be(&offer.public_key);
+                        validate_public_key(&server_public_key, &self.generator, &self.prime)?;
 
-                OsRng.try_fill_bytes(&mut private_key_bytes)?;
+                        let exponent_size = match self.dh_group {
+                            Some(group) => group.exponent_size(),
+                            None => exponent_size(self.key_size as usize),
+                        };
+                        self.private_key = vec![0u8; exponent_size];
 
-                self.private_key = BigUint::from_bytes_be(&private_key_bytes);
+                        OsRng.try_fill_bytes(&mut self.private_key)?;
 
-                let public_key = self.generator.modpow(&self.private_key, &self.prime);
+                        // The generator of a known group has its powers precomputed
+                        let public_key = match self.dh_group {
+                            Some(group) => generator_table(group)?.pow(&self.private_key)?,
+                            None => modpow(&self.generator, &self.private_key, &self.prime)?,
+                        };
+
+                        self.secret_key = modpow(&server_public_key, &self.private_key, &self.prime)?;
+
+                        public_key
+                    }
+                    KeyExchange::X25519 => {
+                        let (private_key, public_key) = x25519_key_pair()?;
+                        self.x25519_private_key = private_key;
+                        self.secret_key = x25519_shared_secret(&self.x25519_private_key, &offer.public_key)?;
+
+                        public_key.to_vec()
+                    }
+                };
 
                 OsRng.try_fill_bytes(&mut self.client_nonce)?;
 
                 self.server_nonce = offer.nonce;
-                self.secret_key = BigUint::from_bytes_be(&offer.public_key)
-                    .modpow(&self.private_key, &self.prime)
-                    .to_bytes_be();
 
                 self.derive_keys();
 
@@ -554,21 +1014,12 @@ impl Srd {
                 let cbt_data = self.compute_cbt(&self.client_nonce)?;
 
                 // Accept
-                let mut common_ciphers = Vec::new();
-                for c in &server_ciphers {
-                    if self.supported_ciphers.contains(c) {
-                        common_ciphers.push(*c);
-                    }
-                }
-
-                self.cipher = Cipher::best_cipher(&common_ciphers)?;
-
                 let mut out_msg = new_srd_accept_msg(
                     self.seq_num,
                     self.use_cbt,
                     self.cipher.flag(),
                     key_size,
-                    public_key.to_bytes_be(),
+                    public_key,
                     self.client_nonce,
                     cbt_data,
                 );
@@ -597,15 +1048,25 @@ impl Srd {
 
                 self.cipher = *chosen_cipher.get(0).unwrap_or(&Cipher::XChaCha20);
 
-                if !self.supported_ciphers.contains(&self.cipher) {
+                if !self.config.ciphers().contains(&self.cipher) {
                     return Err(SrdError::Cipher);
                 }
 
                 self.client_nonce = accept.nonce;
 
-                self.secret_key = BigUint::from_bytes_be(&accept.public_key)
-                    .modpow(&self.private_key, &self.prime)
-                    .to_bytes_be();
+                self.secret_key = match self.key_exchange {
+                    KeyExchange::FiniteField => {
+                        if accept.key_size() != self.key_size {
+                            return Err(SrdError::InvalidKeySize);
+                        }
+
+                        let client_public_key = BigUint::from_bytes_be(&accept.public_key);
+                        validate_public_key(&client_public_key, &self.generator, &self.prime)?;
+
+                        modpow(&client_public_key, &self.private_key, &self.prime)?
+                    }
+                    KeyExchange::X25519 => x25519_shared_secret(&self.x25519_private_key, &accept.public_key)?,
+                };
 
                 self.derive_keys();
 
@@ -622,6 +1083,9 @@ impl Srd {
                 // Generate server cbt
                 let cbt_data = self.compute_cbt(&self.server_nonce)?;
                 let mut out_msg = new_srd_confirm_msg(self.seq_num, self.use_cbt, cbt_data);
+                if self.use_blob_types && !self.config.skip_delegation() {
+                    out_msg.set_blob_types(self.config.accepted_blob_types());
+                }
 
                 self.write_msg(&mut out_msg, &mut output_data)?;
                 Ok(())
@@ -645,19 +1109,36 @@ impl Srd {
                 }
 
                 if !hdr.has_skip() {
+                    self.accepted_blob_types = confirm.blob_types.unwrap_or_default();
+
+                    // The server is authenticated, credentials can be asked for
+                    if let Some(provider) = self.blob_provider.clone() {
+                        let context = DelegationContext::new(
+                            self.cipher,
+                            self.use_cbt,
+                            self.version,
+                            self.accepted_blob_types.clone(),
+                        );
+                        self.blob = Some(provider.provide(&context)?);
+                    }
+
                     // Build Delegate message
                     let mut out_msg = match self.blob {
                         None => {
                             return Err(SrdError::MissingBlob);
                         }
-                        Some(ref b) => new_srd_delegate_msg(
-                            self.seq_num,
-                            self.use_cbt,
-                            b,
-                            self.cipher,
-                            &self.delegation_key,
-                            &self.iv,
-                        )?,
+                        Some(ref b) => {
+                            // Nothing is encrypted for a server that would refuse the blob anyway
+                            check_blob_type(b, &self.accepted_blob_types)?;
+                            new_srd_delegate_msg(
+                                self.seq_num,
+                                self.use_cbt,
+                                b,
+                                self.cipher,
+                                &self.delegation_key,
+                                &self.iv,
+                            )?
+                        }
                     };
 
                     self.write_msg(&mut out_msg, &mut output_data)?;
@@ -673,7 +1154,7 @@ impl Srd {
 
     // Server delegate -> result
     fn server_authenticate_2(&mut self, input_data: &[u8]) -> Result<()> {
-        if self.skip_delegation {
+        if self.config.skip_delegation() {
             return Err(SrdError::BadSequence);
         }
 
@@ -681,7 +1162,9 @@ impl Srd {
         let input_msg = self.read_msg(input_data)?;
         match input_msg {
             SrdMessage::Delegate(_hdr, delegate) => {
-                self.blob = Some(delegate.get_data(self.cipher, &self.delegation_key, &self.iv)?);
+                let blob = delegate.get_data(self.cipher, &self.delegation_key, &self.iv)?;
+                check_blob_type(&blob, self.config.accepted_blob_types())?;
+                self.blob = Some(blob);
 
                 Ok(())
             }
@@ -689,50 +1172,124 @@ impl Srd {
         }
     }
 
-    fn find_dh_parameters(&mut self) -> Result<()> {
-        match self.key_size {
-            256 => {
-                self.generator = BigUint::from_bytes_be(SRD_DH_PARAMS[0].g_data);
-                self.prime = BigUint::from_bytes_be(SRD_DH_PARAMS[0].p_data);
-                Ok(())
-            }
-            512 => {
-                self.generator = BigUint::from_bytes_be(SRD_DH_PARAMS[1].g_data);
-                self.prime = BigUint::from_bytes_be(SRD_DH_PARAMS[1].p_data);
-                Ok(())
-            }
-            1024 => {
-                self.generator = BigUint::from_bytes_be(SRD_DH_PARAMS[2].g_data);
-                self.prime = BigUint::from_bytes_be(SRD_DH_PARAMS[2].p_data);
+    // Client result
+    fn client_authenticate_3(&mut self, input_data: &[u8]) -> Result<()> {
+        let input_msg = self.read_msg(input_data)?;
+        match input_msg {
+            SrdMessage::Result(_hdr, result) => {
+                self.delegation_result = Some(result.result()?);
                 Ok(())
             }
-            _ => Err(SrdError::InvalidKeySize),
+            _ => Err(SrdError::BadSequence),
         }
     }
 
+    #[cfg(not(feature = "wasm"))]
+    fn pooled_key_pair(&self, group: DhGroup) -> Option<(Vec<u8>, Vec<u8>)> {
+        self.key_pool.as_ref().and_then(|key_pool| key_pool.take(group))
+    }
+
+    #[cfg(feature = "wasm")]
+    fn pooled_key_pair(&self, _group: DhGroup) -> Option<(Vec<u8>, Vec<u8>)> {
+        None
+    }
+
     fn derive_keys(&mut self) {
+        let mut salt = [0u8; 64];
+        salt[..32].copy_from_slice(&self.client_nonce);
+        salt[32..].copy_from_slice(&self.server_nonce);
+        let hkdf = Hkdf::extract(&salt, &self.secret_key);
+
+        let mut hash = Sha256::new();
+        for message in self.messages.iter().take(2) {
+            hash.input(message);
+        }
+        let transcript_hash = hash.result();
+
+        match self.version {
+            ProtocolVersion::V1 => self.derive_legacy_keys(),
+            ProtocolVersion::V2 => self.derive_hkdf_keys(&hkdf, &transcript_hash),
+        }
+
+        // Whatever the version, exported keys don't depend on the keys of the handshake
+        hkdf.expand(&[b"srd exporter secret", &transcript_hash], &mut self.exporter_secret);
+
+        // Only the derived keys are needed from now on
+        self.private_key.zeroize();
+        self.x25519_private_key.zeroize();
+        self.secret_key.zeroize();
+    }
+
+    fn derive_legacy_keys(&mut self) {
+        let secret_key = match self.key_exchange {
+            KeyExchange::FiniteField => trim_leading_zeros(&self.secret_key),
+            KeyExchange::X25519 => &self.secret_key,
+        };
+
         let mut hash = Sha256::new();
         hash.input(&self.client_nonce);
-        hash.input(&self.secret_key);
+        hash.input(secret_key);
         hash.input(&self.server_nonce);
 
-        self.delegation_key.clone_from_slice(&hash.result().to_vec());
+        let mut digest = hash.result();
+        self.delegation_key.copy_from_slice(&digest);
+        digest.as_mut_slice().zeroize();
 
         hash = Sha256::new();
         hash.input(&self.server_nonce);
-        hash.input(&self.secret_key);
+        hash.input(secret_key);
         hash.input(&self.client_nonce);
 
-        self.integrity_key.clone_from_slice(&hash.result().to_vec());
+        let mut digest = hash.result();
+        self.integrity_key.copy_from_slice(&digest);
+        digest.as_mut_slice().zeroize();
 
         hash = Sha256::new();
         hash.input(&self.client_nonce);
         hash.input(&self.server_nonce);
 
-        self.iv.clone_from_slice(&hash.result().to_vec());
+        self.iv.copy_from_slice(&hash.result());
+    }
+
+    // Every key is expanded with its own label from the shared secret salted with both nonces. The context binds
+    // the keys to the initiate and offer messages, as sent, and to the cipher chosen by the client.
+    fn derive_hkdf_keys(&mut self, hkdf: &Hkdf, transcript_hash: &[u8]) {
+        let cipher = self.cipher.flag().to_le_bytes();
+
+        hkdf.expand(&[b"srd delegation key", transcript_hash, &cipher], &mut self.delegation_key);
+        hkdf.expand(&[b"srd integrity key", transcript_hash, &cipher], &mut self.integrity_key);
+        hkdf.expand(&[b"srd iv", transcript_hash, &cipher], &mut self.iv);
+    }
+}
+
+impl Drop for Srd {
+    fn drop(&mut self) {
+        self.private_key.zeroize();
+        self.x25519_private_key.zeroize();
+        self.secret_key.zeroize();
+        self.delegation_key.zeroize();
+        self.integrity_key.zeroize();
+        self.iv.zeroize();
+        self.exporter_secret.zeroize();
     }
 }
 
+// Any blob type is accepted when none is listed
+fn check_blob_type(blob: &SrdBlob, accepted_blob_types: &[String]) -> Result<()> {
+    if accepted_blob_types.is_empty() || accepted_blob_types.iter().any(|name| name == blob.blob_type()) {
+        Ok(())
+    } else {
+        Err(SrdError::UnsupportedBlobType(blob.blob_type().to_owned()))
+    }
+}
+
+// The legacy key derivation hashes the shared secret as encoded by num-bigint, without leading zeros. Later
+// versions use the fixed-length secret, whose encoding doesn't depend on its value.
+fn trim_leading_zeros(secret_key: &[u8]) -> &[u8] {
+    let zeros = secret_key.iter().take_while(|byte| **byte == 0).count();
+    &secret_key[zeros..]
+}
+
 #[cfg(feature = "wasm")]
 pub fn fill_random(data: &mut [u8]) -> Result<()> {
     let mut new_data = getrandom(data.to_vec());
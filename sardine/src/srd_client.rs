@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use blobs::{Blob, SrdBlob};
+use cipher::Cipher;
+use credentials::BlobProvider;
+use dh_group::DhGroup;
+use key_exchange::KeyExchange;
+use messages::DelegationResult;
+use protocol_version::ProtocolVersion;
+use session::SrdSession;
+use srd::Srd;
+use srd_config::SrdConfig;
+use srd_errors::SrdError;
+use srd_state::SrdStep;
+use Result;
+
+/// Client side of a handshake that hasn't started yet.
+///
+/// Each step consumes the current state and returns the next one along with the message to send to the server,
+/// so keys can only be read once the handshake is done and the blob can only be set before it starts.
+pub struct SrdClient {
+    srd: Srd,
+}
+
+/// The Initiate message has been sent, waiting for the server Offer.
+pub struct ClientInitiated {
+    srd: Srd,
+}
+
+/// The Accept message has been sent, waiting for the server Confirm.
+pub struct ClientAccepted {
+    srd: Srd,
+}
+
+/// The Delegate message has been sent, waiting for the server Result.
+pub struct ClientDelegated {
+    srd: Srd,
+}
+
+/// The handshake is complete.
+pub struct ClientDone {
+    srd: Srd,
+}
+
+/// State reached after the Confirm message, which depends on whether a delegation result was negotiated.
+///
+/// The Delegate message to send comes along, `Done` has none when delegation is skipped.
+pub enum ClientDelegation {
+    AwaitingResult(ClientDelegated, Vec<u8>),
+    Done(ClientDone, Option<Vec<u8>>),
+}
+
+impl SrdClient {
+    pub fn new(skip_delegation: bool) -> SrdClient {
+        SrdClient {
+            srd: Srd::_new(false, skip_delegation),
+        }
+    }
+
+    pub fn with_config(config: Arc<SrdConfig>) -> SrdClient {
+        SrdClient {
+            srd: Srd::with_config(false, config),
+        }
+    }
+
+    pub fn set_ciphers(&mut self, ciphers: Vec<Cipher>) -> Result<()> {
+        self.srd.set_ciphers(ciphers)
+    }
+
+    pub fn set_cert_data(&mut self, buffer: Vec<u8>) -> Result<()> {
+        self.srd._set_cert_data(buffer)
+    }
+
+    pub fn set_blob<T: Blob>(&mut self, blob: T) -> Result<()> {
+        self.srd.set_blob(blob)
+    }
+
+    pub fn set_raw_blob(&mut self, blob: SrdBlob) {
+        self.srd.set_raw_blob(blob)
+    }
+
+    /// Build the blob when the server Confirm is handled instead, see `BlobProvider`.
+    pub fn set_blob_provider(&mut self, provider: Arc<dyn BlobProvider>) {
+        self.srd.set_blob_provider(provider)
+    }
+
+    /// Produce the Initiate message.
+    pub fn initiate(mut self) -> Result<(ClientInitiated, Vec<u8>)> {
+        match self.srd.step(&[])? {
+            SrdStep::Send(output_data) => Ok((ClientInitiated { srd: self.srd }, output_data)),
+            _ => Err(unexpected_step()),
+        }
+    }
+}
+
+impl ClientInitiated {
+    /// Handle the server Offer and produce the Accept message.
+    pub fn accept(mut self, offer: &[u8]) -> Result<(ClientAccepted, Vec<u8>)> {
+        match self.srd.step(offer)? {
+            SrdStep::Send(output_data) => Ok((ClientAccepted { srd: self.srd }, output_data)),
+            _ => Err(unexpected_step()),
+        }
+    }
+}
+
+impl ClientAccepted {
+    /// Handle the server Confirm and produce the Delegate message, unless delegation is skipped.
+    pub fn delegate(mut self, confirm: &[u8]) -> Result<ClientDelegation> {
+        match self.srd.step(confirm)? {
+            SrdStep::Send(output_data) => Ok(ClientDelegation::AwaitingResult(
+                ClientDelegated { srd: self.srd },
+                output_data,
+            )),
+            SrdStep::SendAndComplete(output_data) => {
+                Ok(ClientDelegation::Done(ClientDone { srd: self.srd }, Some(output_data)))
+            }
+            SrdStep::Complete { .. } => Ok(ClientDelegation::Done(ClientDone { srd: self.srd }, None)),
+            _ => Err(unexpected_step()),
+        }
+    }
+}
+
+impl ClientDelegated {
+    /// Handle the server Result, see `ClientDone::get_delegation_result`.
+    pub fn receive_result(mut self, result: &[u8]) -> Result<ClientDone> {
+        match self.srd.step(result)? {
+            SrdStep::Complete { .. } => Ok(ClientDone { srd: self.srd }),
+            _ => Err(unexpected_step()),
+        }
+    }
+}
+
+impl ClientDone {
+    pub fn get_delegation_result(&self) -> Option<&DelegationResult> {
+        self.srd.get_delegation_result()
+    }
+
+    pub fn get_keys(&self) -> ([u8; 32], [u8; 32]) {
+        self.srd._get_keys()
+    }
+
+    pub fn get_delegation_key(&self) -> Vec<u8> {
+        self.srd.get_delegation_key()
+    }
+
+    pub fn get_integrity_key(&self) -> Vec<u8> {
+        self.srd.get_integrity_key()
+    }
+
+    pub fn get_cipher(&self) -> Cipher {
+        self.srd.get_cipher()
+    }
+
+    pub fn get_key_exchange(&self) -> KeyExchange {
+        self.srd.get_key_exchange()
+    }
+
+    pub fn get_dh_group(&self) -> Option<DhGroup> {
+        self.srd.get_dh_group()
+    }
+
+    pub fn get_version(&self) -> ProtocolVersion {
+        self.srd.get_version()
+    }
+
+    /// Start a secure channel with the peer.
+    pub fn session(&self) -> Result<SrdSession> {
+        SrdSession::new(&self.srd)
+    }
+
+    /// Derive keying material for an upper-layer protocol, see `Srd::export_keying_material`.
+    pub fn export_keying_material(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>> {
+        self.srd._export_keying_material(label, context, length)
+    }
+
+    pub fn into_srd(self) -> Srd {
+        self.srd
+    }
+}
+
+fn unexpected_step() -> SrdError {
+    SrdError::Internal("Unexpected handshake step".to_owned())
+}
@@ -0,0 +1,417 @@
+use std::time::Duration;
+
+use cipher::Cipher;
+use dh_group::DhGroup;
+use key_exchange::KeyExchange;
+use protocol_version::ProtocolVersion;
+use srd_errors::SrdError;
+use Result;
+
+const DEFAULT_KEY_SIZE: u16 = 256;
+
+// Large enough for a Delegate message carrying a blob at DELEGATE_MESSAGE_SIZE_LIMIT.
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 17 * 1024;
+
+/// Settings shared by every handshake of a client or a server.
+///
+/// A configuration is validated once by `SrdConfigBuilder::build` and is meant to be wrapped in an `Arc` and
+/// handed to as many `Srd` instances as needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
+pub struct SrdConfig {
+    key_size: u16,
+    ciphers: Vec<Cipher>,
+    key_exchanges: Vec<KeyExchange>,
+    dh_group: DhGroup,
+    min_version: ProtocolVersion,
+    max_version: ProtocolVersion,
+    cert_data: Option<Vec<u8>>,
+    skip_delegation: bool,
+    send_alerts: bool,
+    delegation_result: bool,
+    accepted_blob_types: Vec<String>,
+    max_message_size: usize,
+    handshake_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    rekey_after_bytes: Option<u64>,
+    rekey_after_messages: Option<u64>,
+    rekey_interval: Option<Duration>,
+}
+
+impl SrdConfig {
+    pub fn builder() -> SrdConfigBuilder {
+        SrdConfigBuilder {
+            config: SrdConfig::default(),
+        }
+    }
+
+    /// Diffie-Hellman key size in bytes. A client requests this size, a server refuses anything smaller.
+    pub fn key_size(&self) -> u16 {
+        self.key_size
+    }
+
+    /// Allowed ciphers, most preferred first.
+    pub fn ciphers(&self) -> &[Cipher] {
+        &self.ciphers
+    }
+
+    /// Allowed key exchanges, most preferred first. A server picks its most preferred one supported by the client.
+    pub fn key_exchanges(&self) -> &[KeyExchange] {
+        &self.key_exchanges
+    }
+
+    /// Group offered by a server to clients that can identify it. Older clients get the legacy group of the key
+    /// size they request.
+    pub fn dh_group(&self) -> DhGroup {
+        self.dh_group
+    }
+
+    /// Lowest protocol version accepted from peers. Raising it refuses peers that can't negotiate a version, or
+    /// that would negotiate an older one.
+    pub fn min_version(&self) -> ProtocolVersion {
+        self.min_version
+    }
+
+    /// Highest protocol version negotiated with peers. Version 1 peers don't advertise any version, setting it
+    /// keeps the messages of the handshake as they were before the negotiation.
+    pub fn max_version(&self) -> ProtocolVersion {
+        self.max_version
+    }
+
+    pub fn cert_data(&self) -> Option<&[u8]> {
+        self.cert_data.as_deref()
+    }
+
+    pub fn skip_delegation(&self) -> bool {
+        self.skip_delegation
+    }
+
+    /// Whether a failed handshake produces an alert message telling the peer why it failed.
+    pub fn send_alerts(&self) -> bool {
+        self.send_alerts
+    }
+
+    /// Whether the handshake ends with a Result message from the server telling whether the delegated blob was
+    /// accepted. Both sides have to enable it, it is ignored when delegation is skipped.
+    pub fn delegation_result(&self) -> bool {
+        self.delegation_result
+    }
+
+    /// Blob types a server accepts, any type when empty. They are sent to clients in the Confirm message so they can
+    /// pick a blob the server accepts, and other blobs are refused with `SrdError::UnsupportedBlobType`.
+    pub fn accepted_blob_types(&self) -> &[String] {
+        &self.accepted_blob_types
+    }
+
+    /// Largest handshake message accepted from the peer, in bytes.
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Time allowed for a whole handshake. `Srd` doesn't do any I/O, this is honored by transport drivers.
+    pub fn handshake_timeout(&self) -> Option<Duration> {
+        self.handshake_timeout
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write_timeout
+    }
+
+    /// Data sealed by a session, in bytes, before it asks for its keys to be rotated.
+    pub fn rekey_after_bytes(&self) -> Option<u64> {
+        self.rekey_after_bytes
+    }
+
+    /// Records sealed by a session before it asks for its keys to be rotated.
+    pub fn rekey_after_messages(&self) -> Option<u64> {
+        self.rekey_after_messages
+    }
+
+    /// Time a session keeps its keys before it asks for them to be rotated.
+    pub fn rekey_interval(&self) -> Option<Duration> {
+        self.rekey_interval
+    }
+
+    pub(crate) fn set_ciphers(&mut self, ciphers: Vec<Cipher>) -> Result<()> {
+        validate_ciphers(&ciphers)?;
+        self.ciphers = ciphers;
+        Ok(())
+    }
+
+    pub(crate) fn set_cert_data(&mut self, cert_data: Vec<u8>) {
+        self.cert_data = Some(cert_data);
+    }
+
+    pub(crate) fn set_skip_delegation(&mut self, skip_delegation: bool) {
+        self.skip_delegation = skip_delegation;
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self.key_size {
+            256 | 512 | 1024 => {}
+            _ => return Err(SrdError::InvalidKeySize),
+        }
+
+        validate_ciphers(&self.ciphers)?;
+
+        if self.min_version > self.max_version {
+            return Err(SrdError::UnsupportedVersion);
+        }
+
+        if self.key_exchanges.is_empty() {
+            return Err(SrdError::KeyExchange);
+        }
+
+        // X25519 isn't a FIPS approved key agreement
+        if cfg!(feature = "fips") && self.key_exchanges.contains(&KeyExchange::X25519) {
+            return Err(SrdError::KeyExchange);
+        }
+
+        // Blob type names are sent null-terminated, one byte per character
+        let is_blob_type = |name: &String| !name.is_empty() && name.bytes().all(|c| c.is_ascii() && c != 0);
+        let blob_types_size: usize = self.accepted_blob_types.iter().map(|name| name.len() + 1).sum();
+        if !self.accepted_blob_types.iter().all(is_blob_type) || blob_types_size > u16::MAX as usize {
+            return Err(SrdError::BlobFormatError);
+        }
+
+        // The Offer message is the largest one that doesn't depend on the blob. Header (8), ciphers (4),
+        // key size (2), group id (2), generator size (2), generator, prime and public key (key size each), nonce (32)
+        // and version (2).
+        let key_size = std::cmp::max(self.key_size, self.dh_group.key_size()) as usize;
+        let offer_size = 52 + self.dh_group.generator().len() + 2 * key_size;
+        if self.max_message_size < offer_size {
+            return Err(SrdError::InvalidDataLength);
+        }
+
+        Ok(())
+    }
+}
+
+// The parameters of `Srd::new` before the configuration existed, so that its messages don't change
+impl Default for SrdConfig {
+    fn default() -> Self {
+        let ciphers = if cfg!(feature = "fips") {
+            vec![Cipher::AES256]
+        } else if cfg!(feature = "aes") {
+            vec![Cipher::XChaCha20, Cipher::ChaCha20, Cipher::AES256]
+        } else {
+            vec![Cipher::XChaCha20, Cipher::ChaCha20]
+        };
+
+        SrdConfig {
+            key_size: DEFAULT_KEY_SIZE,
+            ciphers,
+            key_exchanges: vec![KeyExchange::FiniteField],
+            dh_group: DhGroup::Legacy2048,
+            min_version: ProtocolVersion::V1,
+            max_version: ProtocolVersion::V1,
+            cert_data: None,
+            skip_delegation: false,
+            send_alerts: false,
+            delegation_result: false,
+            accepted_blob_types: Vec::new(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            handshake_timeout: None,
+            read_timeout: None,
+            write_timeout: None,
+            rekey_after_bytes: None,
+            rekey_after_messages: None,
+            rekey_interval: None,
+        }
+    }
+}
+
+pub struct SrdConfigBuilder {
+    config: SrdConfig,
+}
+
+impl SrdConfigBuilder {
+    /// Use the most recent protocol version, authenticated ciphers, X25519 and an RFC 7919 group rather than the
+    /// defaults, which are the parameters of older peers. Older peers still negotiate with the resulting
+    /// configuration.
+    pub fn modern(mut self) -> Self {
+        // Authenticated ciphers first, the others are only there for older peers
+        self.config.ciphers = if cfg!(feature = "fips") {
+            vec![Cipher::AES256GCM, Cipher::AES256]
+        } else if cfg!(feature = "aes") {
+            vec![
+                Cipher::XChaCha20Poly1305,
+                Cipher::ChaCha20Poly1305,
+                Cipher::AES256GCM,
+                Cipher::XChaCha20,
+                Cipher::ChaCha20,
+                Cipher::AES256,
+            ]
+        } else {
+            vec![
+                Cipher::XChaCha20Poly1305,
+                Cipher::ChaCha20Poly1305,
+                Cipher::XChaCha20,
+                Cipher::ChaCha20,
+            ]
+        };
+
+        self.config.key_exchanges = if cfg!(feature = "fips") {
+            vec![KeyExchange::FiniteField]
+        } else {
+            vec![KeyExchange::X25519, KeyExchange::FiniteField]
+        };
+
+        self.config.dh_group = DhGroup::Ffdhe2048;
+        self.config.max_version = ProtocolVersion::latest();
+        self
+    }
+
+    pub fn key_size(mut self, key_size: u16) -> Self {
+        self.config.key_size = key_size;
+        self
+    }
+
+    pub fn ciphers(mut self, ciphers: Vec<Cipher>) -> Self {
+        self.config.ciphers = ciphers;
+        self
+    }
+
+    pub fn key_exchanges(mut self, key_exchanges: Vec<KeyExchange>) -> Self {
+        self.config.key_exchanges = key_exchanges;
+        self
+    }
+
+    pub fn dh_group(mut self, dh_group: DhGroup) -> Self {
+        self.config.dh_group = dh_group;
+        self
+    }
+
+    pub fn min_version(mut self, min_version: ProtocolVersion) -> Self {
+        self.config.min_version = min_version;
+        self
+    }
+
+    pub fn max_version(mut self, max_version: ProtocolVersion) -> Self {
+        self.config.max_version = max_version;
+        self
+    }
+
+    pub fn cert_data(mut self, cert_data: Vec<u8>) -> Self {
+        self.config.cert_data = Some(cert_data);
+        self
+    }
+
+    pub fn skip_delegation(mut self, skip_delegation: bool) -> Self {
+        self.config.skip_delegation = skip_delegation;
+        self
+    }
+
+    pub fn send_alerts(mut self, send_alerts: bool) -> Self {
+        self.config.send_alerts = send_alerts;
+        self
+    }
+
+    pub fn delegation_result(mut self, delegation_result: bool) -> Self {
+        self.config.delegation_result = delegation_result;
+        self
+    }
+
+    pub fn accepted_blob_types(mut self, blob_types: Vec<String>) -> Self {
+        self.config.accepted_blob_types = blob_types;
+        self
+    }
+
+    pub fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.config.max_message_size = max_message_size;
+        self
+    }
+
+    pub fn handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.config.handshake_timeout = Some(timeout);
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.config.read_timeout = Some(timeout);
+        self
+    }
+
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.config.write_timeout = Some(timeout);
+        self
+    }
+
+    pub fn rekey_after_bytes(mut self, bytes: u64) -> Self {
+        self.config.rekey_after_bytes = Some(bytes);
+        self
+    }
+
+    pub fn rekey_after_messages(mut self, messages: u64) -> Self {
+        self.config.rekey_after_messages = Some(messages);
+        self
+    }
+
+    pub fn rekey_interval(mut self, interval: Duration) -> Self {
+        self.config.rekey_interval = Some(interval);
+        self
+    }
+
+    pub fn build(self) -> Result<SrdConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+fn validate_ciphers(ciphers: &[Cipher]) -> Result<()> {
+    if ciphers.is_empty() {
+        return Err(SrdError::Cipher);
+    }
+
+    let is_aes = |c: &Cipher| *c == Cipher::AES256 || *c == Cipher::AES256GCM;
+
+    if cfg!(feature = "fips") && !ciphers.iter().all(is_aes) {
+        return Err(SrdError::Cipher);
+    }
+
+    if cfg!(not(feature = "aes")) && ciphers.iter().any(is_aes) {
+        return Err(SrdError::Cipher);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use cipher::Cipher;
+    use protocol_version::ProtocolVersion;
+    use srd_config::SrdConfig;
+
+    #[test]
+    fn config_validation() {
+        assert!(SrdConfig::builder().key_size(512).build().is_ok());
+        assert!(SrdConfig::builder().key_size(300).build().is_err());
+        assert!(SrdConfig::builder().ciphers(Vec::new()).build().is_err());
+        assert!(SrdConfig::builder().key_exchanges(Vec::new()).build().is_err());
+        assert!(SrdConfig::builder().max_message_size(64).build().is_err());
+        assert!(SrdConfig::builder().accepted_blob_types(vec![String::new()]).build().is_err());
+        assert!(SrdConfig::builder().accepted_blob_types(vec!["Log\0on".to_owned()]).build().is_err());
+        assert!(SrdConfig::builder()
+            .min_version(ProtocolVersion::V2)
+            .max_version(ProtocolVersion::V1)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "fips"))]
+    fn config_cipher_order() {
+        let config = SrdConfig::builder()
+            .ciphers(vec![Cipher::ChaCha20, Cipher::XChaCha20])
+            .skip_delegation(true)
+            .build()
+            .unwrap();
+        assert_eq!(config.ciphers(), [Cipher::ChaCha20, Cipher::XChaCha20]);
+        assert!(config.skip_delegation());
+    }
+}
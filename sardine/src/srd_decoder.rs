@@ -0,0 +1,167 @@
+use std::io::Cursor;
+
+use messages::{message_size, Message, MessageSize, SrdMessage};
+use srd_config::SrdConfig;
+use srd_errors::SrdError;
+use Result;
+
+/// Reassembles SRD messages from byte chunks of any size.
+///
+/// Bytes are appended with `feed` as they arrive from the transport and complete messages are taken out with
+/// `decode`, as received, leaving any extra bytes for the next message. Errors are not recoverable, the transport should be
+/// closed.
+#[derive(Debug, Clone)]
+pub struct SrdDecoder {
+    buffer: Vec<u8>,
+    max_message_size: usize,
+}
+
+impl SrdDecoder {
+    pub fn new(max_message_size: usize) -> SrdDecoder {
+        SrdDecoder {
+            buffer: Vec::new(),
+            max_message_size,
+        }
+    }
+
+    pub fn with_config(config: &SrdConfig) -> SrdDecoder {
+        SrdDecoder::new(config.max_message_size())
+    }
+
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Bytes buffered and not yet decoded.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Minimum number of bytes to feed before the next message can be decoded, 0 if it is already complete.
+    ///
+    /// While the fields holding the message size haven't been received, only the bytes needed to reach them are
+    /// counted.
+    pub fn bytes_needed(&self) -> Result<usize> {
+        let size = self.next_message_size()?;
+        Ok(size.saturating_sub(self.buffer.len()))
+    }
+
+    /// Take the next complete message out of the buffer, if there is one.
+    ///
+    /// The message is parsed to check its length but returned as received, ready to be given to `Srd::step`.
+    pub fn decode(&mut self) -> Result<Option<Vec<u8>>> {
+        let size = self.next_message_size()?;
+        if self.buffer.len() < size {
+            return Ok(None);
+        }
+
+        {
+            let mut reader = Cursor::new(&self.buffer[..size]);
+            SrdMessage::read_from(&mut reader)?;
+            if reader.position() as usize != size {
+                return Err(SrdError::InvalidDataLength);
+            }
+        }
+
+        Ok(Some(self.buffer.drain(..size).collect()))
+    }
+
+    // Size of the next message once it is known, otherwise the size needed to know it
+    fn next_message_size(&self) -> Result<usize> {
+        match message_size(&self.buffer)? {
+            MessageSize::Complete(size) if size > self.max_message_size => Err(SrdError::InvalidDataLength),
+            MessageSize::Complete(size) | MessageSize::Partial(size) => Ok(size),
+        }
+    }
+}
+
+impl Default for SrdDecoder {
+    fn default() -> Self {
+        SrdDecoder::with_config(&SrdConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use blobs::LogonBlob;
+    use srd::Srd;
+    use srd_decoder::SrdDecoder;
+    use srd_errors::SrdError;
+
+    // Feed `data` in chunks of `chunk_size` bytes and return the decoded messages
+    fn decode_chunks(decoder: &mut SrdDecoder, data: &[u8], chunk_size: usize) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+        for chunk in data.chunks(chunk_size) {
+            decoder.feed(chunk);
+            while let Some(message) = decoder.decode().unwrap() {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+
+    #[test]
+    fn decode_fragmented_handshake() {
+        let mut client = Srd::new(false, false);
+        let mut server = Srd::new(true, false);
+        client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+        let mut client_decoder = SrdDecoder::default();
+        let mut server_decoder = SrdDecoder::default();
+
+        let mut client_out = Vec::new();
+        let mut server_out = Vec::new();
+        assert!(!client.authenticate(&[], &mut client_out).unwrap());
+
+        while !client.state().is_complete() || !server.state().is_complete() {
+            for message in decode_chunks(&mut server_decoder, &client_out, 7) {
+                server.authenticate(&message, &mut server_out).unwrap();
+            }
+            client_out.clear();
+
+            for message in decode_chunks(&mut client_decoder, &server_out, 7) {
+                client.authenticate(&message, &mut client_out).unwrap();
+            }
+            server_out.clear();
+        }
+
+        assert_eq!(client.get_keys(), server.get_keys());
+    }
+
+    #[test]
+    fn decode_exact_lengths() {
+        let mut client = Srd::new(false, false);
+        let mut initiate = Vec::new();
+        client.authenticate(&[], &mut initiate).unwrap();
+
+        let mut decoder = SrdDecoder::default();
+        assert_eq!(decoder.bytes_needed().unwrap(), 8);
+
+        // Two messages and the start of a third in a single chunk
+        let mut data = initiate.clone();
+        data.extend_from_slice(&initiate);
+        data.extend_from_slice(&initiate[..3]);
+        decoder.feed(&data);
+
+        assert_eq!(decoder.decode().unwrap().unwrap(), initiate);
+        assert_eq!(decoder.decode().unwrap().unwrap(), initiate);
+        assert!(decoder.decode().unwrap().is_none());
+        assert_eq!(decoder.buffered(), 3);
+        assert_eq!(decoder.bytes_needed().unwrap(), 5);
+
+        decoder.feed(&initiate[3..]);
+        assert_eq!(decoder.bytes_needed().unwrap(), 0);
+        assert_eq!(decoder.decode().unwrap().unwrap(), initiate);
+    }
+
+    #[test]
+    fn decode_oversized_message() {
+        let mut decoder = SrdDecoder::new(64);
+        // Delegate header announcing a 1 KB blob
+        decoder.feed(&[0x53, 0x52, 0x44, 0x00, 5, 4, 1, 0, 0x00, 0x04, 0x00, 0x00]);
+
+        match decoder.decode() {
+            Err(SrdError::InvalidDataLength) => {}
+            _ => panic!("oversized message should be rejected"),
+        }
+    }
+}
@@ -7,6 +7,7 @@ use std::io::Error;
 use std::string::FromUtf8Error;
 
 use chacha;
+use messages::AlertCode;
 use rand;
 
 #[derive(Debug)]
@@ -18,6 +19,7 @@ pub enum SrdError {
     MissingBlob,
     BlobFormatError,
     Cipher,
+    KeyExchange,
     Rng,
     InvalidKeySize,
     InvalidMac,
@@ -27,11 +29,120 @@ pub enum SrdError {
     InvalidCstr,
     InvalidDataLength,
     InvalidSignature,
+    UntrustedDhGroup,
+    WeakDhGenerator,
+    PublicKeyOutOfRange,
+    PublicKeyNotInSubgroup,
+    UnsupportedVersion,
+    UnsupportedBlobType(String),
     UnknownMsgType,
+    PeerAlert(AlertCode),
     Proto(String),
     Internal(String),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
+pub enum SrdErrorKind {
+    Io,
+    Ffi,
+    BadSequence,
+    Crypto,
+    MissingBlob,
+    BlobFormatError,
+    Cipher,
+    KeyExchange,
+    Rng,
+    InvalidKeySize,
+    InvalidMac,
+    InvalidCbt,
+    InvalidCert,
+    InvalidCredentials,
+    InvalidCstr,
+    InvalidDataLength,
+    InvalidSignature,
+    UntrustedDhGroup,
+    WeakDhGenerator,
+    PublicKeyOutOfRange,
+    PublicKeyNotInSubgroup,
+    UnsupportedVersion,
+    UnsupportedBlobType,
+    UnknownMsgType,
+    PeerAlert,
+    Proto,
+    Internal,
+}
+
+impl SrdError {
+    pub fn kind(&self) -> SrdErrorKind {
+        match *self {
+            SrdError::Io(_) => SrdErrorKind::Io,
+            SrdError::Ffi(_) => SrdErrorKind::Ffi,
+            SrdError::BadSequence => SrdErrorKind::BadSequence,
+            SrdError::Crypto => SrdErrorKind::Crypto,
+            SrdError::MissingBlob => SrdErrorKind::MissingBlob,
+            SrdError::BlobFormatError => SrdErrorKind::BlobFormatError,
+            SrdError::Cipher => SrdErrorKind::Cipher,
+            SrdError::KeyExchange => SrdErrorKind::KeyExchange,
+            SrdError::Rng => SrdErrorKind::Rng,
+            SrdError::InvalidKeySize => SrdErrorKind::InvalidKeySize,
+            SrdError::InvalidMac => SrdErrorKind::InvalidMac,
+            SrdError::InvalidCbt => SrdErrorKind::InvalidCbt,
+            SrdError::InvalidCert => SrdErrorKind::InvalidCert,
+            SrdError::InvalidCredentials => SrdErrorKind::InvalidCredentials,
+            SrdError::InvalidCstr => SrdErrorKind::InvalidCstr,
+            SrdError::InvalidDataLength => SrdErrorKind::InvalidDataLength,
+            SrdError::InvalidSignature => SrdErrorKind::InvalidSignature,
+            SrdError::UntrustedDhGroup => SrdErrorKind::UntrustedDhGroup,
+            SrdError::WeakDhGenerator => SrdErrorKind::WeakDhGenerator,
+            SrdError::PublicKeyOutOfRange => SrdErrorKind::PublicKeyOutOfRange,
+            SrdError::PublicKeyNotInSubgroup => SrdErrorKind::PublicKeyNotInSubgroup,
+            SrdError::UnsupportedVersion => SrdErrorKind::UnsupportedVersion,
+            SrdError::UnsupportedBlobType(_) => SrdErrorKind::UnsupportedBlobType,
+            SrdError::UnknownMsgType => SrdErrorKind::UnknownMsgType,
+            SrdError::PeerAlert(_) => SrdErrorKind::PeerAlert,
+            SrdError::Proto(_) => SrdErrorKind::Proto,
+            SrdError::Internal(_) => SrdErrorKind::Internal,
+        }
+    }
+}
+
+// std::io::Error can't be cloned, so a copy keeps its kind and message only. This is enough to report the
+// same failure again once a handshake is in the Failed state.
+impl Clone for SrdError {
+    fn clone(&self) -> Self {
+        match *self {
+            SrdError::Io(ref error) => SrdError::Io(Error::new(error.kind(), error.to_string())),
+            SrdError::Ffi(ref error) => SrdError::Ffi(error.clone()),
+            SrdError::BadSequence => SrdError::BadSequence,
+            SrdError::Crypto => SrdError::Crypto,
+            SrdError::MissingBlob => SrdError::MissingBlob,
+            SrdError::BlobFormatError => SrdError::BlobFormatError,
+            SrdError::Cipher => SrdError::Cipher,
+            SrdError::KeyExchange => SrdError::KeyExchange,
+            SrdError::Rng => SrdError::Rng,
+            SrdError::InvalidKeySize => SrdError::InvalidKeySize,
+            SrdError::InvalidMac => SrdError::InvalidMac,
+            SrdError::InvalidCbt => SrdError::InvalidCbt,
+            SrdError::InvalidCert => SrdError::InvalidCert,
+            SrdError::InvalidCredentials => SrdError::InvalidCredentials,
+            SrdError::InvalidCstr => SrdError::InvalidCstr,
+            SrdError::InvalidDataLength => SrdError::InvalidDataLength,
+            SrdError::InvalidSignature => SrdError::InvalidSignature,
+            SrdError::UntrustedDhGroup => SrdError::UntrustedDhGroup,
+            SrdError::WeakDhGenerator => SrdError::WeakDhGenerator,
+            SrdError::PublicKeyOutOfRange => SrdError::PublicKeyOutOfRange,
+            SrdError::PublicKeyNotInSubgroup => SrdError::PublicKeyNotInSubgroup,
+            SrdError::UnsupportedVersion => SrdError::UnsupportedVersion,
+            SrdError::UnsupportedBlobType(ref blob_type) => SrdError::UnsupportedBlobType(blob_type.clone()),
+            SrdError::UnknownMsgType => SrdError::UnknownMsgType,
+            SrdError::PeerAlert(code) => SrdError::PeerAlert(code),
+            SrdError::Proto(ref desc) => SrdError::Proto(desc.clone()),
+            SrdError::Internal(ref desc) => SrdError::Internal(desc.clone()),
+        }
+    }
+}
+
 impl fmt::Display for SrdError {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self {
@@ -42,6 +153,7 @@ impl fmt::Display for SrdError {
             &SrdError::MissingBlob => write!(f, "Blob error"),
             &SrdError::BlobFormatError => write!(f, "Blob format error"),
             &SrdError::Cipher => write!(f, "Cipher error"),
+            &SrdError::KeyExchange => write!(f, "Key exchange error"),
             &SrdError::Rng => write!(f, "RNG error"),
             &SrdError::InvalidKeySize => write!(f, "Key Size error"),
             &SrdError::InvalidMac => write!(f, "MAC error"),
@@ -51,7 +163,14 @@ impl fmt::Display for SrdError {
             &SrdError::InvalidCstr => write!(f, "String encoding error"),
             &SrdError::InvalidDataLength => write!(f, "Data length error"),
             &SrdError::InvalidSignature => write!(f, "Signature error"),
+            &SrdError::UntrustedDhGroup => write!(f, "Untrusted DH group"),
+            &SrdError::WeakDhGenerator => write!(f, "Weak DH generator"),
+            &SrdError::PublicKeyOutOfRange => write!(f, "Public key range error"),
+            &SrdError::PublicKeyNotInSubgroup => write!(f, "Public key subgroup error"),
+            &SrdError::UnsupportedVersion => write!(f, "Protocol version error"),
+            SrdError::UnsupportedBlobType(blob_type) => write!(f, "Blob type error: {}", blob_type),
             &SrdError::UnknownMsgType => write!(f, "Unknown message type"),
+            &SrdError::PeerAlert(code) => write!(f, "Peer alert: {:?}", code),
             &SrdError::Proto(ref desc) => write!(f, "Protocol error: {}", desc),
             &SrdError::Internal(ref desc) => write!(f, "Internal error: {}", desc),
         }
@@ -68,6 +187,7 @@ impl std::error::Error for SrdError {
             SrdError::MissingBlob => "No blob specified",
             SrdError::BlobFormatError => "Blob format error",
             SrdError::Cipher => "There is a problem with supported ciphers",
+            SrdError::KeyExchange => "There is a problem with supported key exchanges",
             SrdError::Rng => "Couldn't generate random keys",
             SrdError::InvalidKeySize => "Key size must be 256, 512 or 1024",
             SrdError::InvalidMac => "Message authentication code is invalid",
@@ -77,7 +197,14 @@ impl std::error::Error for SrdError {
             SrdError::InvalidCstr => "Username or password is not null-terminated",
             SrdError::InvalidDataLength => "The length of the data to be encrypted or decrypted is invalid",
             SrdError::InvalidSignature => "Packet signature is invalid",
+            SrdError::UntrustedDhGroup => "DH group is unknown and doesn't use a safe prime",
+            SrdError::WeakDhGenerator => "DH generator is of order 1 or 2",
+            SrdError::PublicKeyOutOfRange => "Peer public key is out of range",
+            SrdError::PublicKeyNotInSubgroup => "Peer public key is not in the expected subgroup",
+            SrdError::UnsupportedVersion => "No protocol version is supported by both sides",
+            SrdError::UnsupportedBlobType(_) => "The server doesn't accept the type of the delegated blob",
             SrdError::UnknownMsgType => "Unknown message type",
+            SrdError::PeerAlert(_) => "The peer reported a failure of the handshake",
             SrdError::Proto(_) => "Protocol error",
             SrdError::Internal(_) => "Internal error",
         }
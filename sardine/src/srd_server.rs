@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use blobs::{Blob, SrdBlob};
+use cipher::Cipher;
+use credentials::CredentialValidator;
+use dh_group::DhGroup;
+#[cfg(not(feature = "wasm"))]
+use ephemeral_pool::EphemeralKeyPool;
+use key_exchange::KeyExchange;
+use messages::DelegationResult;
+use protocol_version::ProtocolVersion;
+use session::SrdSession;
+use srd::Srd;
+use srd_config::SrdConfig;
+use srd_errors::SrdError;
+use srd_state::SrdStep;
+use Result;
+
+/// Server side of a handshake that hasn't received anything yet.
+///
+/// Each step consumes the current state and returns the next one, so keys and the delegated blob are only
+/// reachable from `ServerDone`.
+pub struct SrdServer {
+    srd: Srd,
+}
+
+/// The Offer message has been sent, waiting for the client Accept.
+pub struct ServerOffered {
+    srd: Srd,
+}
+
+/// The Confirm message has been sent, waiting for the client Delegate.
+pub struct ServerConfirmed {
+    srd: Srd,
+}
+
+/// The Delegate message has been handled, waiting for the application to accept or refuse the blob.
+pub struct ServerDelegated {
+    srd: Srd,
+}
+
+/// The handshake is complete.
+pub struct ServerDone {
+    srd: Srd,
+}
+
+/// State reached after the Confirm message, which depends on whether delegation is skipped.
+pub enum ServerConfirmation {
+    AwaitingDelegate(ServerConfirmed),
+    Done(ServerDone),
+}
+
+/// State reached after the Delegate message, which depends on whether the application decides on the delegation
+/// result.
+///
+/// `Done` comes with the Result message to send when the credential validator decided on it.
+pub enum ServerDelegation {
+    AwaitingResult(ServerDelegated),
+    Done(ServerDone, Option<Vec<u8>>),
+}
+
+impl SrdServer {
+    pub fn new(skip_delegation: bool) -> SrdServer {
+        SrdServer {
+            srd: Srd::_new(true, skip_delegation),
+        }
+    }
+
+    pub fn with_config(config: Arc<SrdConfig>) -> SrdServer {
+        SrdServer {
+            srd: Srd::with_config(true, config),
+        }
+    }
+
+    pub fn set_ciphers(&mut self, ciphers: Vec<Cipher>) -> Result<()> {
+        self.srd.set_ciphers(ciphers)
+    }
+
+    pub fn set_cert_data(&mut self, buffer: Vec<u8>) -> Result<()> {
+        self.srd._set_cert_data(buffer)
+    }
+
+    /// Check the delegated blob before the handshake completes, see `CredentialValidator`.
+    pub fn set_credential_validator(&mut self, validator: Arc<dyn CredentialValidator>) {
+        self.srd.set_credential_validator(validator)
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    pub fn set_key_pool(&mut self, key_pool: Arc<EphemeralKeyPool>) {
+        self.srd.set_key_pool(key_pool)
+    }
+
+    /// Handle the client Initiate and produce the Offer message.
+    pub fn offer(mut self, initiate: &[u8]) -> Result<(ServerOffered, Vec<u8>)> {
+        match self.srd.step(initiate)? {
+            SrdStep::Send(output_data) => Ok((ServerOffered { srd: self.srd }, output_data)),
+            _ => Err(unexpected_step()),
+        }
+    }
+}
+
+impl ServerOffered {
+    /// Handle the client Accept and produce the Confirm message.
+    pub fn confirm(mut self, accept: &[u8]) -> Result<(ServerConfirmation, Vec<u8>)> {
+        match self.srd.step(accept)? {
+            SrdStep::Send(output_data) => Ok((
+                ServerConfirmation::AwaitingDelegate(ServerConfirmed { srd: self.srd }),
+                output_data,
+            )),
+            SrdStep::SendAndComplete(output_data) => {
+                Ok((ServerConfirmation::Done(ServerDone { srd: self.srd }), output_data))
+            }
+            _ => Err(unexpected_step()),
+        }
+    }
+}
+
+impl ServerConfirmed {
+    /// Handle the client Delegate message.
+    pub fn receive_delegate(mut self, delegate: &[u8]) -> Result<ServerDelegation> {
+        match self.srd.step(delegate)? {
+            SrdStep::Continue => Ok(ServerDelegation::AwaitingResult(ServerDelegated { srd: self.srd })),
+            SrdStep::SendAndComplete(output_data) => {
+                Ok(ServerDelegation::Done(ServerDone { srd: self.srd }, Some(output_data)))
+            }
+            SrdStep::Complete { .. } => Ok(ServerDelegation::Done(ServerDone { srd: self.srd }, None)),
+            _ => Err(unexpected_step()),
+        }
+    }
+}
+
+impl ServerDelegated {
+    pub fn get_blob<T: Blob>(&self) -> Result<Option<T>> {
+        self.srd.get_blob()
+    }
+
+    pub fn get_raw_blob(&self) -> Option<SrdBlob> {
+        self.srd.get_raw_blob()
+    }
+
+    /// Produce the Result message telling the client whether its blob was accepted.
+    pub fn send_result(mut self, result: DelegationResult) -> Result<(ServerDone, Vec<u8>)> {
+        let output_data = self.srd._set_delegation_result(result)?;
+        Ok((ServerDone { srd: self.srd }, output_data))
+    }
+}
+
+impl ServerDone {
+    pub fn get_keys(&self) -> ([u8; 32], [u8; 32]) {
+        self.srd._get_keys()
+    }
+
+    pub fn get_delegation_key(&self) -> Vec<u8> {
+        self.srd.get_delegation_key()
+    }
+
+    pub fn get_integrity_key(&self) -> Vec<u8> {
+        self.srd.get_integrity_key()
+    }
+
+    pub fn get_cipher(&self) -> Cipher {
+        self.srd.get_cipher()
+    }
+
+    pub fn get_key_exchange(&self) -> KeyExchange {
+        self.srd.get_key_exchange()
+    }
+
+    pub fn get_dh_group(&self) -> Option<DhGroup> {
+        self.srd.get_dh_group()
+    }
+
+    pub fn get_version(&self) -> ProtocolVersion {
+        self.srd.get_version()
+    }
+
+    /// Start a secure channel with the peer.
+    pub fn session(&self) -> Result<SrdSession> {
+        SrdSession::new(&self.srd)
+    }
+
+    /// Derive keying material for an upper-layer protocol, see `Srd::export_keying_material`.
+    pub fn export_keying_material(&self, label: &[u8], context: &[u8], length: usize) -> Result<Vec<u8>> {
+        self.srd._export_keying_material(label, context, length)
+    }
+
+    pub fn get_blob<T: Blob>(&self) -> Result<Option<T>> {
+        self.srd.get_blob()
+    }
+
+    pub fn get_raw_blob(&self) -> Option<SrdBlob> {
+        self.srd.get_raw_blob()
+    }
+
+    pub fn into_srd(self) -> Srd {
+        self.srd
+    }
+}
+
+fn unexpected_step() -> SrdError {
+    SrdError::Internal("Unexpected handshake step".to_owned())
+}
@@ -0,0 +1,74 @@
+use blobs::SrdBlob;
+use messages::SrdMessageType;
+use srd_errors::SrdErrorKind;
+
+/// Progress of a handshake, as seen by one side of the exchange.
+///
+/// A client starts in `Initial` and a server in `AwaitingInitiate`. Every call to `Srd::authenticate` moves the
+/// state forward by one step until `Complete`. Any error moves it to `Failed`, which is terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ser", derive(Serialize, Deserialize))]
+pub enum SrdState {
+    /// Client only: the Initiate message has not been produced yet.
+    Initial,
+    AwaitingInitiate,
+    AwaitingOffer,
+    AwaitingAccept,
+    AwaitingConfirm,
+    AwaitingDelegate,
+    /// Server only: the Delegate message has been handled, waiting for `Srd::set_delegation_result`.
+    AwaitingDelegationResult,
+    /// Client only: the Delegate message has been sent, waiting for the server Result.
+    AwaitingResult,
+    Complete,
+    Failed(SrdErrorKind),
+}
+
+impl SrdState {
+    pub fn expected_next_message(&self) -> Option<SrdMessageType> {
+        match *self {
+            SrdState::AwaitingInitiate => Some(SrdMessageType::Initiate),
+            SrdState::AwaitingOffer => Some(SrdMessageType::Offer),
+            SrdState::AwaitingAccept => Some(SrdMessageType::Accept),
+            SrdState::AwaitingConfirm => Some(SrdMessageType::Confirm),
+            SrdState::AwaitingDelegate => Some(SrdMessageType::Delegate),
+            SrdState::AwaitingResult => Some(SrdMessageType::Result),
+            SrdState::Initial | SrdState::AwaitingDelegationResult | SrdState::Complete | SrdState::Failed(_) => None,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        *self == SrdState::Complete
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(*self, SrdState::Failed(_))
+    }
+}
+
+/// Outcome of feeding one input to `Srd::step`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SrdStep {
+    /// Send this message to the peer and wait for its answer.
+    Send(Vec<u8>),
+    /// Send this message to the peer. Nothing else is expected, the handshake is complete.
+    SendAndComplete(Vec<u8>),
+    /// The handshake is complete and there is nothing left to send. On a server, `blob` holds the delegated blob.
+    Complete { blob: Option<SrdBlob> },
+    /// The input was consumed but there is nothing to send yet. More input is needed, or a server waiting for the
+    /// delegation result gets the message to send from `Srd::set_delegation_result`.
+    Continue,
+}
+
+impl SrdStep {
+    pub fn output_data(&self) -> Option<&[u8]> {
+        match *self {
+            SrdStep::Send(ref data) | SrdStep::SendAndComplete(ref data) => Some(data),
+            SrdStep::Complete { .. } | SrdStep::Continue => None,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(*self, SrdStep::SendAndComplete(_) | SrdStep::Complete { .. })
+    }
+}
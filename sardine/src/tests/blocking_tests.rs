@@ -0,0 +1,135 @@
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use blobs::LogonBlob;
+use io::{handshake_client, handshake_server, send_delegation_result, Pipe};
+use messages::DelegationResult;
+use srd::Srd;
+use srd_config::SrdConfig;
+use srd_errors::SrdError;
+
+#[test]
+fn blocking_handshake() {
+    let config = Arc::new(SrdConfig::default());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let logon_blob = LogonBlob::new("fdubois", "1234567");
+
+    let server_config = config.clone();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut srd = Srd::with_config(true, server_config);
+        handshake_server(&mut stream, &mut srd)
+    });
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut srd = Srd::with_config(false, config);
+    srd.set_blob(logon_blob.clone()).unwrap();
+    let client = handshake_client(&mut stream, &mut srd).unwrap();
+    let server = server.join().unwrap().unwrap();
+
+    assert!(srd.state().is_complete());
+    assert_eq!(client.get_keys(), server.get_keys());
+    assert_eq!(client.get_cipher(), server.get_cipher());
+    assert_eq!(server.get_blob::<LogonBlob>().unwrap().unwrap(), logon_blob);
+
+    // Neither the keys nor the delegated credentials are printed
+    let debug = format!("{:?}", server);
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains(&format!("{:?}", server.get_keys().0)));
+}
+
+#[test]
+#[cfg(unix)]
+fn blocking_handshake_skip_delegation() {
+    use std::os::unix::net::UnixStream;
+
+    let config = Arc::new(SrdConfig::builder().skip_delegation(true).build().unwrap());
+    let (mut client_stream, mut server_stream) = UnixStream::pair().unwrap();
+
+    let server_config = config.clone();
+    let server = thread::spawn(move || {
+        let mut srd = Srd::with_config(true, server_config);
+        handshake_server(&mut server_stream, &mut srd)
+    });
+
+    let client = handshake_client(&mut client_stream, &mut Srd::with_config(false, config)).unwrap();
+    let server = server.join().unwrap().unwrap();
+
+    assert_eq!(client.get_integrity_key(), server.get_integrity_key());
+    assert!(server.get_raw_blob().is_none());
+}
+
+#[test]
+fn blocking_handshake_pipe() {
+    let config = Arc::new(SrdConfig::default());
+    let (client_reader, server_writer) = std::io::pipe().unwrap();
+    let (server_reader, client_writer) = std::io::pipe().unwrap();
+
+    let server_config = config.clone();
+    let server = thread::spawn(move || {
+        let mut srd = Srd::with_config(true, server_config);
+        handshake_server(&mut Pipe::new(server_reader, server_writer), &mut srd)
+    });
+
+    let mut srd = Srd::with_config(false, config);
+    srd.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+    let client = handshake_client(&mut Pipe::new(client_reader, client_writer), &mut srd).unwrap();
+    let server = server.join().unwrap().unwrap();
+
+    assert_eq!(client.get_keys(), server.get_keys());
+    assert!(server.get_raw_blob().is_some());
+}
+
+#[test]
+fn blocking_delegation_result() {
+    let config = Arc::new(SrdConfig::builder().delegation_result(true).build().unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let server_config = config.clone();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut srd = Srd::with_config(true, server_config);
+        let outcome = handshake_server(&mut stream, &mut srd).unwrap();
+        let accepted = outcome.get_blob::<LogonBlob>().unwrap().unwrap().get_password() == "1234567";
+        let result = if accepted {
+            DelegationResult::success(Vec::new())
+        } else {
+            DelegationResult::failure(Vec::new())
+        };
+        send_delegation_result(&mut stream, &mut srd, result).unwrap();
+        srd.state()
+    });
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    let mut srd = Srd::with_config(false, config);
+    srd.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+    handshake_client(&mut stream, &mut srd).unwrap();
+
+    assert!(server.join().unwrap().is_complete());
+    assert!(srd.get_delegation_result().unwrap().is_success());
+}
+
+#[test]
+fn blocking_read_timeout() {
+    let config = SrdConfig::builder()
+        .read_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    // Nobody answers on the other end
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+    let mut srd = Srd::with_config(false, Arc::new(config));
+    match handshake_client(&mut stream, &mut srd) {
+        Err(SrdError::Io(ref e)) => assert_eq!(e.kind(), ErrorKind::TimedOut),
+        _ => panic!("handshake should have timed out"),
+    }
+
+    // The stream timeouts are restored
+    assert_eq!(stream.read_timeout().unwrap(), None);
+}
@@ -0,0 +1,119 @@
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+use tokio::runtime::{Builder, Runtime};
+
+use blobs::{LogonBlob, SrdBlob};
+use credentials::CredentialValidator;
+use io::SrdStream;
+use messages::DelegationResult;
+use srd_config::SrdConfig;
+use srd_errors::SrdError;
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().enable_time().build().unwrap()
+}
+
+#[test]
+fn async_handshake() {
+    let runtime = runtime();
+    let config = Arc::new(SrdConfig::default());
+    let (client_io, server_io) = duplex(64);
+    let logon_blob = LogonBlob::new("fdubois", "1234567");
+
+    let server = runtime.spawn(SrdStream::server(server_io, config.clone()));
+    let client = SrdStream::client(client_io, config)
+        .with_blob(logon_blob.clone())
+        .unwrap();
+
+    let (mut client_io, client) = runtime.block_on(client).unwrap();
+    let (mut server_io, server) = runtime.block_on(server).unwrap().unwrap();
+
+    assert_eq!(client.get_keys(), server.get_keys());
+    assert_eq!(client.get_cipher(), server.get_cipher());
+    assert_eq!(server.get_blob::<LogonBlob>().unwrap().unwrap(), logon_blob);
+
+    // The streams are handed back untouched
+    runtime.block_on(client_io.write_all(b"ping")).unwrap();
+    let mut buffer = [0u8; 4];
+    runtime.block_on(server_io.read_exact(&mut buffer)).unwrap();
+    assert_eq!(&buffer, b"ping");
+}
+
+#[test]
+fn async_handshake_skip_delegation() {
+    let runtime = runtime();
+    let config = Arc::new(SrdConfig::builder().skip_delegation(true).build().unwrap());
+    let (client_io, server_io) = duplex(1024);
+
+    let server = runtime.spawn(SrdStream::server(server_io, config.clone()));
+    let (_, client) = runtime.block_on(SrdStream::client(client_io, config)).unwrap();
+    let (_, server) = runtime.block_on(server).unwrap().unwrap();
+
+    assert_eq!(client.get_integrity_key(), server.get_integrity_key());
+    assert!(server.get_raw_blob().is_none());
+}
+
+struct RefuseAll;
+
+impl CredentialValidator for RefuseAll {
+    fn validate(&self, _blob: &SrdBlob) -> ::Result<DelegationResult> {
+        Ok(DelegationResult::failure(b"wrong password".to_vec()))
+    }
+}
+
+#[test]
+fn async_handshake_refused_credentials() {
+    let runtime = runtime();
+    let config = Arc::new(SrdConfig::builder().delegation_result(true).build().unwrap());
+    let (client_io, server_io) = duplex(1024);
+
+    let server = SrdStream::server(server_io, config.clone())
+        .with_credential_validator(Arc::new(RefuseAll))
+        .unwrap();
+    let server = runtime.spawn(server);
+    let client = SrdStream::client(client_io, config)
+        .with_blob(LogonBlob::new("fdubois", "1234567"))
+        .unwrap();
+
+    // The client learns about the refusal from the Result message
+    let (_, client) = runtime.block_on(client).unwrap();
+    let result = client.get_delegation_result().unwrap();
+    assert!(!result.is_success());
+    assert_eq!(result.payload(), b"wrong password");
+
+    let (_, server) = runtime.block_on(server).unwrap().unwrap();
+    assert!(!server.get_delegation_result().unwrap().is_success());
+}
+
+#[test]
+fn async_read_timeout() {
+    let runtime = runtime();
+    let config = SrdConfig::builder()
+        .read_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    // Nobody answers on the other end
+    let (client_io, _server_io) = duplex(1024);
+
+    match runtime.block_on(SrdStream::client(client_io, Arc::new(config))) {
+        Err(SrdError::Io(ref e)) => assert_eq!(e.kind(), ErrorKind::TimedOut),
+        _ => panic!("handshake should have timed out"),
+    }
+}
+
+#[test]
+fn async_oversized_message() {
+    let runtime = runtime();
+    let (mut client_io, server_io) = duplex(1024);
+
+    let server = runtime.spawn(SrdStream::server(server_io, Arc::new(SrdConfig::default())));
+    runtime.block_on(client_io.write_all(&[0xff, 0xff, 0xff, 0xff])).unwrap();
+
+    match runtime.block_on(server).unwrap() {
+        Err(SrdError::InvalidDataLength) => {}
+        _ => panic!("oversized message should be rejected"),
+    }
+}
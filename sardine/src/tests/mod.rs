@@ -1,2 +1,7 @@
 #[cfg(test)]
+mod blocking_tests;
+#[cfg(test)]
 mod srd_tests;
+
+#[cfg(all(test, feature = "tokio"))]
+mod io_tests;
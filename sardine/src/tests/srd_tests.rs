@@ -1,6 +1,24 @@
-use blobs::{BasicBlob, LogonBlob};
+use blobs::{BasicBlob, LogonBlob, SrdBlob};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
 use cipher::Cipher;
+use credentials::{BlobProvider, CredentialValidator, DelegationContext};
+use dh_group::DhGroup;
+use ephemeral_pool::EphemeralKeyPool;
+use ffi::{Srd_Decrypt, Srd_Encrypt};
+use key_exchange::KeyExchange;
+use messages::new_srd_initiate_msg;
+use protocol_version::ProtocolVersion;
+use session::SrdSession;
 use srd::Srd;
+use srd_client::{ClientAccepted, ClientDelegation, ClientDone, SrdClient};
+use srd_server::{ServerConfirmation, ServerDelegation, ServerDone, SrdServer};
+use srd_config::SrdConfig;
+use srd_errors::{SrdError, SrdErrorKind};
+use srd_state::{SrdState, SrdStep};
+use {AlertCode, DelegationResult, Message, SrdMessage, SrdMessageType};
 
 static TEST_CERT_DATA: &'static [u8] = b"\x30\x82\x02\xfa\x30\x82\x01\xe2\xa0\x03\x02\x01\x02\x02\x10\x16
 	\xed\x2a\xa0\x49\x5f\x25\x9d\x4f\x5d\x99\xed\xad\xa5\x70\xd1\x30
@@ -180,4 +198,1090 @@ fn skip_delegation() {
 
     assert!(client_status);
     assert!(server_status);
-}
\ No newline at end of file
+}
+
+#[test]
+fn handshake_states() {
+    let mut client = Srd::new(false, false);
+    let mut server = Srd::new(true, false);
+    client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+
+    assert_eq!(client.state(), SrdState::Initial);
+    assert_eq!(client.expected_next_message(), None);
+    assert_eq!(server.state(), SrdState::AwaitingInitiate);
+    assert_eq!(server.expected_next_message(), Some(SrdMessageType::Initiate));
+
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    assert_eq!(client.state(), SrdState::AwaitingOffer);
+
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+    assert_eq!(server.state(), SrdState::AwaitingAccept);
+
+    let mut accept = Vec::new();
+    client.authenticate(&offer, &mut accept).unwrap();
+    assert_eq!(client.state(), SrdState::AwaitingConfirm);
+
+    let mut confirm = Vec::new();
+    server.authenticate(&accept, &mut confirm).unwrap();
+    assert_eq!(server.state(), SrdState::AwaitingDelegate);
+    assert_eq!(server.expected_next_message(), Some(SrdMessageType::Delegate));
+
+    let mut delegate = Vec::new();
+    assert!(client.authenticate(&confirm, &mut delegate).unwrap());
+    assert_eq!(client.state(), SrdState::Complete);
+
+    assert!(server.authenticate(&delegate, &mut Vec::new()).unwrap());
+    assert_eq!(server.state(), SrdState::Complete);
+    assert_eq!(server.expected_next_message(), None);
+
+    // An extra call is refused but doesn't invalidate the completed handshake
+    assert!(server.authenticate(&delegate, &mut Vec::new()).is_err());
+    assert_eq!(server.state(), SrdState::Complete);
+}
+
+#[test]
+fn failure_is_terminal() {
+    let mut client = Srd::new(false, false);
+    let mut server = Srd::new(true, false);
+
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+    let mut accept = Vec::new();
+    client.authenticate(&offer, &mut accept).unwrap();
+
+    // Tamper with the accept MAC
+    let last = accept.len() - 1;
+    accept[last] ^= 0xff;
+
+    let error = server.authenticate(&accept, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidMac);
+    assert_eq!(server.state(), SrdState::Failed(SrdErrorKind::InvalidMac));
+    assert_eq!(server.expected_next_message(), None);
+
+    // Feeding the untouched message afterwards still reports the original failure
+    accept[last] ^= 0xff;
+    let error = server.authenticate(&accept, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidMac);
+    assert_eq!(server.state(), SrdState::Failed(SrdErrorKind::InvalidMac));
+}
+
+#[test]
+fn trailing_bytes_are_rejected() {
+    let mut client = Srd::new(false, false);
+    let mut server = Srd::new(true, false);
+
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+    let mut accept = Vec::new();
+    client.authenticate(&offer, &mut accept).unwrap();
+
+    // The MAC used to be read from the end of the buffer, whatever came before it
+    accept.extend_from_slice(&[0u8; 4]);
+    let error = server.authenticate(&accept, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidDataLength);
+}
+
+// Delegate step of a typed handshake without a delegation result
+fn delegate(client: ClientAccepted, confirm: &[u8]) -> (ClientDone, Vec<u8>) {
+    match client.delegate(confirm).unwrap() {
+        ClientDelegation::Done(client, Some(delegate)) => (client, delegate),
+        _ => panic!("the client should be done once the Delegate message is sent"),
+    }
+}
+
+fn receive_delegate(server: ServerConfirmation, delegate: &[u8]) -> ServerDone {
+    match server {
+        ServerConfirmation::AwaitingDelegate(server) => match server.receive_delegate(delegate).unwrap() {
+            ServerDelegation::Done(server, None) => server,
+            _ => panic!("the server should be done once the Delegate message is received"),
+        },
+        ServerConfirmation::Done(_) => panic!("delegation was not skipped"),
+    }
+}
+
+#[test]
+fn typed_handshake() {
+    let mut client = SrdClient::new(false);
+    let mut server = SrdServer::new(false);
+
+    client.set_cert_data(TEST_CERT_DATA.to_vec()).unwrap();
+    server.set_cert_data(TEST_CERT_DATA.to_vec()).unwrap();
+
+    let logon_blob = LogonBlob::new("fdubois", "1234567ßẞ");
+    client.set_blob(logon_blob.clone()).unwrap();
+
+    let (client, initiate) = client.initiate().unwrap();
+    let (server, offer) = server.offer(&initiate).unwrap();
+    let (client, accept) = client.accept(&offer).unwrap();
+    let (server, confirm) = server.confirm(&accept).unwrap();
+    let (client, delegate) = delegate(client, &confirm);
+    let server = receive_delegate(server, &delegate);
+
+    assert_eq!(client.get_keys(), server.get_keys());
+    assert_eq!(client.get_cipher(), server.get_cipher());
+    assert_eq!(server.get_blob::<LogonBlob>().unwrap().unwrap(), logon_blob);
+}
+
+#[test]
+fn typed_handshake_skip_delegation() {
+    let (client, initiate) = SrdClient::new(true).initiate().unwrap();
+    let (server, offer) = SrdServer::new(true).offer(&initiate).unwrap();
+    let (client, accept) = client.accept(&offer).unwrap();
+    let (server, confirm) = server.confirm(&accept).unwrap();
+    let client = match client.delegate(&confirm).unwrap() {
+        ClientDelegation::Done(client, delegate) => {
+            assert!(delegate.is_none());
+            client
+        }
+        ClientDelegation::AwaitingResult(..) => panic!("no delegation result was negotiated"),
+    };
+
+    match server {
+        ServerConfirmation::Done(server) => assert_eq!(client.get_keys(), server.get_keys()),
+        ServerConfirmation::AwaitingDelegate(_) => panic!("delegation should be skipped"),
+    }
+}
+
+fn send_data(step: SrdStep) -> Vec<u8> {
+    match step {
+        SrdStep::Send(data) => data,
+        _ => panic!("expected a message to send"),
+    }
+}
+
+#[test]
+fn handshake_steps() {
+    let mut client = Srd::new(false, false);
+    let mut server = Srd::new(true, false);
+
+    client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+    let blob = client.get_raw_blob();
+
+    let initiate = send_data(client.step(&[]).unwrap());
+    let offer = send_data(server.step(&initiate).unwrap());
+    let accept = send_data(client.step(&offer).unwrap());
+    let confirm = send_data(server.step(&accept).unwrap());
+
+    let delegate = match client.step(&confirm).unwrap() {
+        SrdStep::SendAndComplete(data) => data,
+        _ => panic!("the delegate message completes the client"),
+    };
+
+    assert_eq!(server.step(&delegate).unwrap(), SrdStep::Complete { blob });
+}
+
+#[test]
+fn skip_delegation_steps() {
+    let mut client = Srd::new(false, true);
+    let mut server = Srd::new(true, true);
+
+    let initiate = send_data(client.step(&[]).unwrap());
+    let offer = send_data(server.step(&initiate).unwrap());
+    let accept = send_data(client.step(&offer).unwrap());
+
+    let confirm = match server.step(&accept).unwrap() {
+        SrdStep::SendAndComplete(data) => data,
+        _ => panic!("the confirm message completes the server"),
+    };
+
+    assert_eq!(client.step(&confirm).unwrap(), SrdStep::Complete { blob: None });
+}
+
+#[test]
+#[cfg(not(feature = "fips"))]
+fn shared_config() {
+    let client_config = Arc::new(
+        SrdConfig::builder()
+            .key_size(512)
+            .ciphers(vec![Cipher::ChaCha20, Cipher::XChaCha20])
+            .cert_data(TEST_CERT_DATA.to_vec())
+            .build()
+            .unwrap(),
+    );
+    let server_config = Arc::new(SrdConfig::builder().cert_data(TEST_CERT_DATA.to_vec()).build().unwrap());
+
+    for _ in 0..2 {
+        let mut client = SrdClient::with_config(client_config.clone());
+        client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+
+        let (client, initiate) = client.initiate().unwrap();
+        let (server, offer) = SrdServer::with_config(server_config.clone()).offer(&initiate).unwrap();
+        let (client, accept) = client.accept(&offer).unwrap();
+        let (server, confirm) = server.confirm(&accept).unwrap();
+        let (client, delegate) = delegate(client, &confirm);
+        let server = receive_delegate(server, &delegate);
+
+        // The client preference order wins
+        assert_eq!(client.get_cipher(), Cipher::ChaCha20);
+        assert_eq!(client.get_keys(), server.get_keys());
+    }
+}
+
+#[test]
+fn server_minimum_key_size() {
+    // The key size only applies to finite-field Diffie-Hellman
+    let client_config = SrdConfig::builder()
+        .key_exchanges(vec![KeyExchange::FiniteField])
+        .build()
+        .unwrap();
+    let mut client = Srd::with_config(false, Arc::new(client_config));
+    let mut server = Srd::with_config(true, Arc::new(SrdConfig::builder().key_size(512).build().unwrap()));
+
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+
+    let error = server.authenticate(&initiate, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidKeySize);
+}
+
+fn run_handshake(client: &mut Srd, server: &mut Srd) {
+    let mut client_out = Vec::new();
+    let mut server_out = Vec::new();
+
+    client.authenticate(&[], &mut client_out).unwrap();
+    while !(client.state().is_complete() && server.state().is_complete()) {
+        server.authenticate(&client_out, &mut server_out).unwrap();
+        client_out.clear();
+        if !client.state().is_complete() {
+            client.authenticate(&server_out, &mut client_out).unwrap();
+        }
+        server_out.clear();
+    }
+}
+
+#[test]
+#[cfg(not(feature = "fips"))]
+fn key_exchange_negotiation() {
+    let finite_field_config = SrdConfig::builder()
+        .key_exchanges(vec![KeyExchange::FiniteField])
+        .skip_delegation(true)
+        .build()
+        .unwrap();
+    let config = SrdConfig::builder().modern().skip_delegation(true).build().unwrap();
+
+    let mut client = Srd::with_config(false, Arc::new(config.clone()));
+    let mut server = Srd::with_config(true, Arc::new(config.clone()));
+    run_handshake(&mut client, &mut server);
+    assert_eq!(client.get_key_exchange(), KeyExchange::X25519);
+    assert_eq!(server.get_key_exchange(), KeyExchange::X25519);
+    assert_eq!(client.get_keys(), server.get_keys());
+
+    let mut client = Srd::with_config(false, Arc::new(finite_field_config));
+    let mut server = Srd::with_config(true, Arc::new(config));
+    run_handshake(&mut client, &mut server);
+    assert_eq!(client.get_key_exchange(), KeyExchange::FiniteField);
+    assert_eq!(server.get_key_exchange(), KeyExchange::FiniteField);
+    assert_eq!(client.get_keys(), server.get_keys());
+}
+
+#[test]
+fn legacy_client_uses_finite_field() {
+    let mut client = Srd::new(false, true);
+    let mut server = Srd::new(true, true);
+
+    // Older clients leave the key exchange flags reserved and don't advertise any version
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    initiate[6] &= !0x20;
+    initiate.truncate(16);
+    initiate[14] = 0;
+    initiate[15] = 0;
+
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+    assert_eq!(server.get_key_exchange(), KeyExchange::FiniteField);
+    assert_eq!(server.get_dh_group(), Some(DhGroup::Legacy2048));
+    // Header, ciphers, key size, generator, prime, public key and nonce
+    assert_eq!(offer.len(), 8 + 8 + 2 * 256 + 32);
+}
+
+#[test]
+fn baseline_initiate_with_default_server() {
+    let ciphers = SrdConfig::default().ciphers().iter().fold(0, |flags, cipher| flags | cipher.flag());
+
+    for key_size in [256, 512, 1024].iter() {
+        // The Initiate message of clients older than the configuration
+        let mut initiate = Vec::new();
+        new_srd_initiate_msg(0, false, ciphers, *key_size, 0)
+            .unwrap()
+            .write_to(&mut initiate)
+            .unwrap();
+
+        let mut server = Srd::new(true, false);
+        let mut offer = Vec::new();
+        server.authenticate(&initiate, &mut offer).unwrap();
+        assert_eq!(server.get_key_exchange(), KeyExchange::FiniteField);
+        assert_eq!(server.get_dh_group(), DhGroup::legacy(*key_size));
+        // Header, ciphers, key size, generator, prime, public key and nonce
+        assert_eq!(offer.len(), 8 + 8 + 2 * *key_size as usize + 32);
+
+        // A default client still sends it, and completes the handshake with a default server
+        let config = Arc::new(SrdConfig::builder().key_size(*key_size).build().unwrap());
+        let mut client_initiate = Vec::new();
+        Srd::with_config(false, config.clone())
+            .authenticate(&[], &mut client_initiate)
+            .unwrap();
+        assert_eq!(client_initiate, initiate);
+
+        let mut client = Srd::with_config(false, config);
+        let mut server = Srd::new(true, false);
+        client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+        run_handshake(&mut client, &mut server);
+        assert_eq!(client.get_version(), ProtocolVersion::V1);
+        assert_eq!(client.get_keys(), server.get_keys());
+    }
+}
+
+#[test]
+fn dh_group_negotiation() {
+    let client_config = SrdConfig::builder()
+        .modern()
+        .key_exchanges(vec![KeyExchange::FiniteField])
+        .skip_delegation(true)
+        .build()
+        .unwrap();
+    let server_config = SrdConfig::builder()
+        .dh_group(DhGroup::from_name("ffdhe3072").unwrap())
+        .skip_delegation(true)
+        .build()
+        .unwrap();
+
+    let mut client = Srd::with_config(false, Arc::new(client_config));
+    let mut server = Srd::with_config(true, Arc::new(server_config));
+    run_handshake(&mut client, &mut server);
+
+    assert_eq!(client.get_dh_group(), Some(DhGroup::Ffdhe3072));
+    assert_eq!(server.get_dh_group(), Some(DhGroup::Ffdhe3072));
+    assert_eq!(client.get_keys(), server.get_keys());
+}
+
+#[test]
+fn dh_group_smaller_than_requested() {
+    let client_config = SrdConfig::builder()
+        .modern()
+        .key_size(512)
+        .key_exchanges(vec![KeyExchange::FiniteField])
+        .build()
+        .unwrap();
+
+    let mut client = Srd::with_config(false, Arc::new(client_config));
+    let mut server = Srd::with_config(true, Arc::new(SrdConfig::builder().modern().build().unwrap()));
+
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    let error = server.authenticate(&initiate, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidKeySize);
+}
+
+#[test]
+fn tampered_offer_is_rejected() {
+    let client_config = Arc::new(
+        SrdConfig::builder()
+            .modern()
+            .key_exchanges(vec![KeyExchange::FiniteField])
+            .build()
+            .unwrap(),
+    );
+
+    let new_client = |initiate: &mut Vec<u8>| {
+        let mut client = Srd::with_config(false, client_config.clone());
+        client.authenticate(&[], initiate).unwrap();
+        client
+    };
+
+    let mut server = Srd::with_config(true, Arc::new(SrdConfig::builder().modern().build().unwrap()));
+    let mut initiate = Vec::new();
+    new_client(&mut initiate);
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+
+    // Header, ciphers, key size, group id, generator size and generator come before the prime
+    let prime_start = 8 + 10 + 1;
+    let public_key_start = prime_start + 256;
+
+    let mut tampered = offer.clone();
+    tampered[prime_start + 255] ^= 0x02;
+    let error = new_client(&mut Vec::new())
+        .authenticate(&tampered, &mut Vec::new())
+        .unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::UntrustedDhGroup);
+
+    let mut tampered = offer.clone();
+    for byte in tampered[public_key_start..public_key_start + 256].iter_mut() {
+        *byte = 0;
+    }
+    tampered[public_key_start + 255] = 1;
+    let error = new_client(&mut Vec::new())
+        .authenticate(&tampered, &mut Vec::new())
+        .unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::PublicKeyOutOfRange);
+}
+
+#[test]
+fn server_key_pool() {
+    let client_config = Arc::new(
+        SrdConfig::builder()
+            .modern()
+            .key_exchanges(vec![KeyExchange::FiniteField])
+            .skip_delegation(true)
+            .build()
+            .unwrap(),
+    );
+    let server_config = Arc::new(SrdConfig::builder().modern().skip_delegation(true).build().unwrap());
+
+    let key_pool = Arc::new(EphemeralKeyPool::new(&[DhGroup::Ffdhe2048], 1, 2).unwrap());
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while key_pool.available(DhGroup::Ffdhe2048) < 2 {
+        assert!(Instant::now() < deadline, "key pool not filled");
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let mut client = Srd::with_config(false, client_config);
+    let mut server = Srd::with_config(true, server_config);
+    server.set_key_pool(key_pool.clone());
+    run_handshake(&mut client, &mut server);
+
+    assert_eq!(client.get_keys(), server.get_keys());
+    assert_eq!(key_pool.available(DhGroup::Ffdhe2048), 1);
+}
+
+#[test]
+fn version_negotiation() {
+    let config = SrdConfig::builder().modern().skip_delegation(true).build().unwrap();
+    let v1_config = SrdConfig::builder()
+        .modern()
+        .max_version(ProtocolVersion::V1)
+        .skip_delegation(true)
+        .build()
+        .unwrap();
+
+    let mut client = Srd::with_config(false, Arc::new(config.clone()));
+    let mut server = Srd::with_config(true, Arc::new(config.clone()));
+    run_handshake(&mut client, &mut server);
+    assert_eq!(client.get_version(), ProtocolVersion::V2);
+    assert_eq!(server.get_version(), ProtocolVersion::V2);
+    assert_eq!(client.get_keys(), server.get_keys());
+
+    // Either side limited to version 1 gets the legacy key derivation
+    for (client_config, server_config) in [(&v1_config, &config), (&config, &v1_config)].iter() {
+        let mut client = Srd::with_config(false, Arc::new((*client_config).clone()));
+        let mut server = Srd::with_config(true, Arc::new((*server_config).clone()));
+        run_handshake(&mut client, &mut server);
+        assert_eq!(client.get_version(), ProtocolVersion::V1);
+        assert_eq!(server.get_version(), ProtocolVersion::V1);
+        assert_eq!(client.get_keys(), server.get_keys());
+    }
+
+    // Version 1 clients don't add anything to the initiate message
+    let mut initiate = Vec::new();
+    Srd::with_config(false, Arc::new(v1_config))
+        .authenticate(&[], &mut initiate)
+        .unwrap();
+    assert_eq!(initiate.len(), 8 + 8);
+}
+
+#[test]
+fn offered_version_is_checked() {
+    let config = Arc::new(SrdConfig::builder().modern().skip_delegation(true).build().unwrap());
+
+    let mut client = Srd::with_config(false, config.clone());
+    let mut server = Srd::with_config(true, config);
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+
+    // A server can't select a version the client doesn't know
+    let version_start = offer.len() - 2;
+    offer[version_start] = 3;
+    let error = client.authenticate(&offer, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::Proto);
+}
+
+#[test]
+fn aead_delegation() {
+    let ciphers = if cfg!(feature = "fips") {
+        vec![Cipher::AES256GCM]
+    } else if cfg!(feature = "aes") {
+        vec![Cipher::XChaCha20Poly1305, Cipher::ChaCha20Poly1305, Cipher::AES256GCM]
+    } else {
+        vec![Cipher::XChaCha20Poly1305, Cipher::ChaCha20Poly1305]
+    };
+
+    // Authenticated ciphers are negotiated by the modern configuration
+    let config = Arc::new(SrdConfig::builder().modern().build().unwrap());
+    let mut client = Srd::with_config(false, config.clone());
+    let mut server = Srd::with_config(true, config.clone());
+    client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+    run_handshake(&mut client, &mut server);
+    assert_eq!(server.get_cipher(), ciphers[0]);
+
+    for cipher in ciphers {
+        let client_config = SrdConfig::builder().modern().ciphers(vec![cipher]).build().unwrap();
+        let mut client = Srd::with_config(false, Arc::new(client_config));
+        let mut server = Srd::with_config(true, config.clone());
+        client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+        run_handshake(&mut client, &mut server);
+
+        assert_eq!(server.get_cipher(), cipher);
+        assert_eq!(
+            server.get_blob::<LogonBlob>().unwrap().unwrap(),
+            LogonBlob::new("fdubois", "1234567")
+        );
+    }
+}
+
+#[test]
+fn session_records() {
+    let mut client = Srd::new(false, true);
+    let mut server = Srd::new(true, true);
+    assert_eq!(SrdSession::new(&client).err().unwrap().kind(), SrdErrorKind::BadSequence);
+    run_handshake(&mut client, &mut server);
+
+    let mut client_session = SrdSession::new(&client).unwrap();
+    let mut server_session = SrdSession::new(&server).unwrap();
+    assert!(client_session.cipher().is_aead());
+
+    let first = client_session.seal(b"first");
+    let second = client_session.seal(b"second");
+    assert_eq!(first.len(), 5 + client_session.overhead());
+
+    // Records must be opened in order, and only once
+    assert_eq!(server_session.open(&second).unwrap_err().kind(), SrdErrorKind::BadSequence);
+    assert_eq!(server_session.open(&first).unwrap(), b"first");
+    assert_eq!(server_session.open(&first).unwrap_err().kind(), SrdErrorKind::BadSequence);
+
+    // A tampered record doesn't move the session forward
+    let mut tampered = second.clone();
+    tampered[10] ^= 1;
+    assert_eq!(server_session.open(&tampered).unwrap_err().kind(), SrdErrorKind::InvalidMac);
+    assert_eq!(server_session.open(&second).unwrap(), b"second");
+
+    // Each direction has its own key
+    let reply = server_session.seal(b"reply");
+    assert!(server_session.open(&reply).is_err());
+    assert_eq!(client_session.open(&reply).unwrap(), b"reply");
+}
+
+#[test]
+fn ffi_session_records() {
+    let mut client = Srd::new(false, true);
+    let mut server = Srd::new(true, true);
+    run_handshake(&mut client, &mut server);
+    let client: *mut Srd = Box::into_raw(Box::new(client));
+    let server: *mut Srd = Box::into_raw(Box::new(server));
+
+    let data = b"application data";
+    let mut record_size = 0;
+    assert_eq!(Srd_Encrypt(client, data.as_ptr(), data.len(), std::ptr::null_mut(), &mut record_size), 0);
+    let mut record = vec![0u8; record_size];
+    assert_eq!(Srd_Encrypt(client, data.as_ptr(), data.len(), record.as_mut_ptr(), &mut record_size), 1);
+
+    let mut output = vec![0u8; data.len()];
+    let mut output_size = output.len();
+    assert_eq!(Srd_Decrypt(server, record.as_ptr(), record.len(), output.as_mut_ptr(), &mut output_size), 1);
+    assert_eq!(&output[..output_size], &data[..]);
+
+    // Replayed records are rejected
+    assert_eq!(Srd_Decrypt(server, record.as_ptr(), record.len(), output.as_mut_ptr(), &mut output_size), -1);
+
+    unsafe {
+        drop(Box::from_raw(client));
+        drop(Box::from_raw(server));
+    }
+}
+
+#[test]
+fn keying_material_exporter() {
+    let v1_config = SrdConfig::builder()
+        .max_version(ProtocolVersion::V1)
+        .skip_delegation(true)
+        .build()
+        .unwrap();
+
+    for config in [Arc::new(v1_config), Arc::new(SrdConfig::builder().skip_delegation(true).build().unwrap())].iter() {
+        let mut client = Srd::with_config(false, config.clone());
+        let mut server = Srd::with_config(true, config.clone());
+        client.authenticate(&[], &mut Vec::new()).unwrap();
+        assert_eq!(
+            client.export_keying_material(b"cookie", b"", 32).unwrap_err().kind(),
+            SrdErrorKind::BadSequence
+        );
+
+        let mut client = Srd::with_config(false, config.clone());
+        run_handshake(&mut client, &mut server);
+
+        let exported = client.export_keying_material(b"cookie", b"session 1", 48).unwrap();
+        assert_eq!(server.export_keying_material(b"cookie", b"session 1", 48).unwrap(), exported);
+        assert_ne!(client.export_keying_material(b"cookie", b"session 2", 48).unwrap(), exported);
+        assert!(!exported.windows(32).any(|window| window == &client.get_delegation_key()[..]));
+    }
+}
+
+#[test]
+fn session_rekey() {
+    let config = SrdConfig::builder()
+        .skip_delegation(true)
+        .rekey_after_messages(2)
+        .rekey_after_bytes(1024)
+        .build()
+        .unwrap();
+    let mut client = Srd::with_config(false, Arc::new(config.clone()));
+    let mut server = Srd::with_config(true, Arc::new(config));
+    run_handshake(&mut client, &mut server);
+
+    let mut client_session = SrdSession::new(&client).unwrap();
+    let mut server_session = SrdSession::new(&server).unwrap();
+
+    let first = client_session.seal(b"first");
+    assert!(!client_session.needs_rekey());
+    let second = client_session.seal(b"second");
+    assert!(client_session.needs_rekey());
+
+    let rekey = client_session.rekey().unwrap();
+    assert!(!client_session.needs_rekey());
+    let third = client_session.seal(b"third");
+
+    // The new keys only apply from the record following the Rekey message
+    assert_eq!(server_session.receive_rekey(&rekey).unwrap_err().kind(), SrdErrorKind::BadSequence);
+    assert_eq!(server_session.open(&first).unwrap(), b"first");
+    assert_eq!(server_session.open(&second).unwrap(), b"second");
+    assert!(server_session.open(&third).is_err());
+
+    let mut tampered = rekey.clone();
+    tampered[20] ^= 1;
+    assert_eq!(server_session.receive_rekey(&tampered).unwrap_err().kind(), SrdErrorKind::InvalidMac);
+
+    server_session.receive_rekey(&rekey).unwrap();
+    assert_eq!(server_session.open(&third).unwrap(), b"third");
+
+    // The other direction keeps its keys
+    let reply = server_session.seal(&[0u8; 1024]);
+    assert!(server_session.needs_rekey());
+    assert_eq!(client_session.open(&reply).unwrap(), vec![0u8; 1024]);
+}
+
+#[test]
+fn session_rekey_interval() {
+    let config = SrdConfig::builder()
+        .skip_delegation(true)
+        .rekey_interval(Duration::from_millis(20))
+        .build()
+        .unwrap();
+    let mut client = Srd::with_config(false, Arc::new(config.clone()));
+    let mut server = Srd::with_config(true, Arc::new(config));
+    run_handshake(&mut client, &mut server);
+
+    let mut session = SrdSession::new(&client).unwrap();
+    assert!(!session.needs_rekey());
+    thread::sleep(Duration::from_millis(30));
+    assert!(session.needs_rekey());
+    session.rekey().unwrap();
+    assert!(!session.needs_rekey());
+}
+
+#[test]
+fn version_downgrade_is_detected() {
+    let config = Arc::new(SrdConfig::builder().modern().skip_delegation(true).build().unwrap());
+    let mut client = Srd::with_config(false, config.clone());
+    let mut server = Srd::with_config(true, config.clone());
+
+    // The version is removed from the initiate message on its way to the server
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    initiate[6] &= !0x20;
+    initiate.truncate(16);
+
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+    assert_eq!(server.get_version(), ProtocolVersion::V1);
+
+    let mut accept = Vec::new();
+    client.authenticate(&offer, &mut accept).unwrap();
+    let error = server.authenticate(&accept, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidMac);
+
+    // Peers requiring a recent version refuse the older ones
+    let strict_config = Arc::new(
+        SrdConfig::builder()
+            .modern()
+            .min_version(ProtocolVersion::V2)
+            .skip_delegation(true)
+            .build()
+            .unwrap(),
+    );
+    let mut server = Srd::with_config(true, strict_config.clone());
+    let error = server.authenticate(&initiate, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::UnsupportedVersion);
+
+    let mut client = Srd::with_config(false, strict_config);
+    let mut server = Srd::with_config(true, config);
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    initiate[6] &= !0x20;
+    initiate.truncate(16);
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+    let error = client.authenticate(&offer, &mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::UnsupportedVersion);
+}
+
+#[test]
+fn alerts_reach_the_peer() {
+    let config = Arc::new(SrdConfig::builder().send_alerts(true).build().unwrap());
+    let mut client = Srd::with_config(false, config.clone());
+    let mut server = Srd::with_config(true, config);
+    client.set_cert_data(TEST_CERT_DATA.to_vec()).unwrap();
+    let mut other_cert = TEST_CERT_DATA.to_vec();
+    other_cert[100] ^= 0xff;
+    server.set_cert_data(other_cert).unwrap();
+
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+    let mut accept = Vec::new();
+    client.authenticate(&offer, &mut accept).unwrap();
+
+    // The server sends an alert instead of the confirm message
+    let mut alert = Vec::new();
+    let error = server.authenticate(&accept, &mut alert).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidCbt);
+    assert_eq!(server.get_alert(), Some(alert.as_slice()));
+
+    let error = client.authenticate(&alert, &mut Vec::new()).unwrap_err();
+    match error {
+        SrdError::PeerAlert(code) => assert_eq!(code, AlertCode::InvalidCbt),
+        _ => panic!("unexpected error {}", error),
+    }
+    assert_eq!(client.state(), SrdState::Failed(SrdErrorKind::PeerAlert));
+    assert_eq!(client.get_peer_alert_text(), Some("CBT error"));
+    // No alert is sent back for an alert
+    assert_eq!(client.get_alert(), None);
+
+    // Alerts are only sent when configured
+    let mut client = Srd::new(false, false);
+    let mut server = Srd::new(true, false);
+    let mut initiate = Vec::new();
+    client.authenticate(&[], &mut initiate).unwrap();
+    let mut offer = Vec::new();
+    server.authenticate(&initiate, &mut offer).unwrap();
+    let mut accept = Vec::new();
+    client.authenticate(&offer, &mut accept).unwrap();
+    let last = accept.len() - 1;
+    accept[last] ^= 0xff;
+
+    let mut output = Vec::new();
+    let error = server.authenticate(&accept, &mut output).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidMac);
+    assert!(output.is_empty());
+    assert_eq!(server.get_alert(), None);
+}
+
+#[test]
+fn delegation_result() {
+    let config = Arc::new(SrdConfig::builder().delegation_result(true).build().unwrap());
+    let mut client = Srd::with_config(false, config.clone());
+    let mut server = Srd::with_config(true, config.clone());
+    client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+
+    let initiate = send_data(client.step(&[]).unwrap());
+    let offer = send_data(server.step(&initiate).unwrap());
+    let accept = send_data(client.step(&offer).unwrap());
+    let confirm = send_data(server.step(&accept).unwrap());
+    let delegate = send_data(client.step(&confirm).unwrap());
+    assert_eq!(client.state(), SrdState::AwaitingResult);
+    assert_eq!(client.expected_next_message(), Some(SrdMessageType::Result));
+
+    // The server checks the blob before completing the handshake
+    assert_eq!(server.step(&delegate).unwrap(), SrdStep::Continue);
+    assert_eq!(server.state(), SrdState::AwaitingDelegationResult);
+    assert!(server.get_blob::<LogonBlob>().unwrap().is_some());
+    let result = server
+        .set_delegation_result(DelegationResult::failure(b"wrong password".to_vec()))
+        .unwrap();
+    assert_eq!(server.state(), SrdState::Complete);
+
+    // The result is authenticated
+    let mut tampered_client = Srd::with_config(false, config.clone());
+    tampered_client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+    let mut tampered_server = Srd::with_config(true, config);
+    let initiate = send_data(tampered_client.step(&[]).unwrap());
+    let offer = send_data(tampered_server.step(&initiate).unwrap());
+    let accept = send_data(tampered_client.step(&offer).unwrap());
+    let confirm = send_data(tampered_server.step(&accept).unwrap());
+    let delegate = send_data(tampered_client.step(&confirm).unwrap());
+    tampered_server.step(&delegate).unwrap();
+    let mut tampered = tampered_server
+        .set_delegation_result(DelegationResult::success(Vec::new()))
+        .unwrap();
+    tampered[8] ^= 0x01;
+    let error = tampered_client.step(&tampered).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::InvalidMac);
+
+    assert!(client.step(&result).unwrap().is_complete());
+    let result = client.get_delegation_result().unwrap();
+    assert!(!result.is_success());
+    assert_eq!(result.payload(), b"wrong password");
+    assert_eq!(client.get_keys(), server.get_keys());
+}
+
+#[test]
+fn delegation_result_needs_both_sides() {
+    // A server that doesn't send results completes on the Delegate message
+    let mut client = Srd::with_config(
+        false,
+        Arc::new(SrdConfig::builder().delegation_result(true).build().unwrap()),
+    );
+    let mut server = Srd::new(true, false);
+    client.set_blob(BasicBlob::new("fdubois", "1234567")).unwrap();
+    run_handshake(&mut client, &mut server);
+    assert!(client.get_delegation_result().is_none());
+    let error = server.set_delegation_result(DelegationResult::success(Vec::new())).unwrap_err();
+    assert_eq!(error.kind(), SrdErrorKind::BadSequence);
+
+    let typed_config = Arc::new(SrdConfig::builder().delegation_result(true).build().unwrap());
+    let mut client = SrdClient::with_config(typed_config.clone());
+    client.set_blob(BasicBlob::new("fdubois", "1234567")).unwrap();
+    let (client, initiate) = client.initiate().unwrap();
+    let (server, offer) = SrdServer::with_config(typed_config).offer(&initiate).unwrap();
+    let (client, accept) = client.accept(&offer).unwrap();
+    let (server, confirm) = server.confirm(&accept).unwrap();
+    let (client, delegate) = match client.delegate(&confirm).unwrap() {
+        ClientDelegation::AwaitingResult(client, delegate) => (client, delegate),
+        ClientDelegation::Done(..) => panic!("a delegation result was negotiated"),
+    };
+    let server = match server {
+        ServerConfirmation::AwaitingDelegate(server) => match server.receive_delegate(&delegate).unwrap() {
+            ServerDelegation::AwaitingResult(server) => server,
+            ServerDelegation::Done(..) => panic!("the application decides on the result"),
+        },
+        ServerConfirmation::Done(_) => panic!("delegation was not skipped"),
+    };
+    let (server, result) = server.send_result(DelegationResult::success(b"welcome".to_vec())).unwrap();
+    let client = client.receive_result(&result).unwrap();
+    assert!(client.get_delegation_result().unwrap().is_success());
+    assert_eq!(client.get_keys(), server.get_keys());
+}
+
+struct PasswordValidator {
+    password: &'static str,
+}
+
+impl CredentialValidator for PasswordValidator {
+    fn validate(&self, blob: &SrdBlob) -> ::Result<DelegationResult> {
+        match blob.get::<LogonBlob>()? {
+            Some(ref logon) if logon.get_password() == self.password => Ok(DelegationResult::success(b"hi".to_vec())),
+            _ => Ok(DelegationResult::failure(Vec::new())),
+        }
+    }
+}
+
+fn validated_handshake(config: Arc<SrdConfig>, password: &str) -> (Srd, Srd, ::Result<SrdStep>) {
+    let mut client = Srd::with_config(false, config.clone());
+    let mut server = Srd::with_config(true, config);
+    client.set_blob(LogonBlob::new("fdubois", password)).unwrap();
+    server.set_credential_validator(Arc::new(PasswordValidator { password: "1234567" }));
+
+    let initiate = send_data(client.step(&[]).unwrap());
+    let offer = send_data(server.step(&initiate).unwrap());
+    let accept = send_data(client.step(&offer).unwrap());
+    let confirm = send_data(server.step(&accept).unwrap());
+    // The Delegate message is the last one without a delegation result
+    let delegate = client.step(&confirm).unwrap().output_data().unwrap().to_vec();
+    let step = server.step(&delegate);
+    (client, server, step)
+}
+
+#[test]
+fn credential_validator() {
+    let config = Arc::new(SrdConfig::default());
+    let (_, server, step) = validated_handshake(config.clone(), "1234567");
+    assert!(step.unwrap().is_complete());
+    assert!(server.get_delegation_result().unwrap().is_success());
+
+    let (_, server, step) = validated_handshake(config, "7654321");
+    assert_eq!(step.unwrap_err().kind(), SrdErrorKind::InvalidCredentials);
+    assert_eq!(server.state(), SrdState::Failed(SrdErrorKind::InvalidCredentials));
+    assert_eq!(server.get_alert(), None);
+
+    // The verdict is sent in the Result message, which completes the handshake either way
+    let config = Arc::new(SrdConfig::builder().delegation_result(true).build().unwrap());
+    let (mut client, _, step) = validated_handshake(config.clone(), "1234567");
+    let result = match step.unwrap() {
+        SrdStep::SendAndComplete(result) => result,
+        step => panic!("unexpected step {:?}", step),
+    };
+    assert!(client.step(&result).unwrap().is_complete());
+    assert_eq!(client.get_delegation_result().unwrap().payload(), b"hi");
+
+    let (mut client, server, step) = validated_handshake(config, "7654321");
+    let result = match step.unwrap() {
+        SrdStep::SendAndComplete(result) => result,
+        step => panic!("unexpected step {:?}", step),
+    };
+    assert_eq!(server.state(), SrdState::Complete);
+    assert!(!server.get_delegation_result().unwrap().is_success());
+    assert_eq!(server.get_alert(), None);
+    assert!(client.step(&result).unwrap().is_complete());
+    assert!(!client.get_delegation_result().unwrap().is_success());
+
+    let config = Arc::new(SrdConfig::builder().send_alerts(true).build().unwrap());
+    let (client, server, step) = validated_handshake(config, "7654321");
+    assert_eq!(step.unwrap_err().kind(), SrdErrorKind::InvalidCredentials);
+    // The client is done with the handshake once the Delegate message is sent, the alert is for the application
+    assert!(client.state().is_complete());
+    let mut alert = std::io::Cursor::new(server.get_alert().unwrap());
+    match SrdMessage::read_from(&mut alert).unwrap() {
+        SrdMessage::Alert(_, alert) => assert_eq!(alert.code(), AlertCode::InvalidCredentials),
+        message => panic!("unexpected message {:?}", message),
+    }
+}
+
+#[test]
+fn typed_credential_validator() {
+    let config = Arc::new(SrdConfig::builder().delegation_result(true).build().unwrap());
+    let mut client = SrdClient::with_config(config.clone());
+    let mut server = SrdServer::with_config(config);
+    client.set_blob(LogonBlob::new("fdubois", "7654321")).unwrap();
+    server.set_credential_validator(Arc::new(PasswordValidator { password: "1234567" }));
+
+    let (client, initiate) = client.initiate().unwrap();
+    let (server, offer) = server.offer(&initiate).unwrap();
+    let (client, accept) = client.accept(&offer).unwrap();
+    let (server, confirm) = server.confirm(&accept).unwrap();
+    let (client, delegate) = match client.delegate(&confirm).unwrap() {
+        ClientDelegation::AwaitingResult(client, delegate) => (client, delegate),
+        ClientDelegation::Done(..) => panic!("a delegation result was negotiated"),
+    };
+
+    // The validator decides on the result, sent along the completed server
+    let (server, result) = match server {
+        ServerConfirmation::AwaitingDelegate(server) => match server.receive_delegate(&delegate).unwrap() {
+            ServerDelegation::Done(server, Some(result)) => (server, result),
+            _ => panic!("the validator decides on the result"),
+        },
+        ServerConfirmation::Done(_) => panic!("delegation was not skipped"),
+    };
+    let client = client.receive_result(&result).unwrap();
+    assert!(!client.get_delegation_result().unwrap().is_success());
+    assert_eq!(client.get_keys(), server.get_keys());
+}
+
+#[derive(Default)]
+struct PromptedLogon {
+    context: Mutex<Option<DelegationContext>>,
+}
+
+impl BlobProvider for PromptedLogon {
+    fn provide(&self, context: &DelegationContext) -> ::Result<SrdBlob> {
+        *self.context.lock().unwrap() = Some(context.clone());
+        let mut data = Vec::new();
+        LogonBlob::new("fdubois", "1234567").write_to(&mut data)?;
+        Ok(SrdBlob::new("Logon", &data))
+    }
+}
+
+#[test]
+fn blob_provider() {
+    let provider = Arc::new(PromptedLogon::default());
+    let mut client = SrdClient::new(false);
+    let mut server = SrdServer::new(false);
+    client.set_cert_data(TEST_CERT_DATA.to_vec()).unwrap();
+    server.set_cert_data(TEST_CERT_DATA.to_vec()).unwrap();
+    client.set_blob_provider(provider.clone());
+
+    let (client, initiate) = client.initiate().unwrap();
+    let (server, offer) = server.offer(&initiate).unwrap();
+    let (client, accept) = client.accept(&offer).unwrap();
+    assert!(provider.context.lock().unwrap().is_none());
+    let (server, confirm) = server.confirm(&accept).unwrap();
+    let (client, delegate) = delegate(client, &confirm);
+    let server = receive_delegate(server, &delegate);
+    assert_eq!(client.get_keys(), server.get_keys());
+    assert_eq!(server.get_blob::<LogonBlob>().unwrap().unwrap().get_password(), "1234567");
+
+    let context = provider.context.lock().unwrap().take().unwrap();
+    assert_eq!(context.cipher(), server.get_cipher());
+    assert!(context.use_cbt());
+    assert_eq!(context.version(), server.get_version());
+
+    // A Confirm that doesn't prove the server holds the shared secret never gets to a prompt
+    let mut client = Srd::new(false, false);
+    let mut server = Srd::new(true, false);
+    client.set_blob_provider(provider.clone());
+
+    let initiate = send_data(client.step(&[]).unwrap());
+    let offer = send_data(server.step(&initiate).unwrap());
+    let accept = send_data(client.step(&offer).unwrap());
+    let mut confirm = send_data(server.step(&accept).unwrap());
+    let last = confirm.len() - 1;
+    confirm[last] ^= 1;
+    assert_eq!(client.step(&confirm).unwrap_err().kind(), SrdErrorKind::InvalidMac);
+    assert!(provider.context.lock().unwrap().is_none());
+}
+
+struct FirstAccepted;
+
+impl BlobProvider for FirstAccepted {
+    fn provide(&self, context: &DelegationContext) -> ::Result<SrdBlob> {
+        let mut data = Vec::new();
+        match context.accepted_blob_types().first().map(|name| name.as_str()) {
+            Some("Basic") => {
+                BasicBlob::new("fdubois", "1234567").write_to(&mut data)?;
+                Ok(SrdBlob::new("Basic", &data))
+            }
+            _ => {
+                LogonBlob::new("fdubois", "1234567").write_to(&mut data)?;
+                Ok(SrdBlob::new("Logon", &data))
+            }
+        }
+    }
+}
+
+#[test]
+fn blob_type_negotiation() {
+    let client_config = Arc::new(SrdConfig::builder().modern().build().unwrap());
+    let config = Arc::new(
+        SrdConfig::builder()
+            .modern()
+            .accepted_blob_types(vec!["Basic".to_owned(), "Custom".to_owned()])
+            .build()
+            .unwrap(),
+    );
+
+    // The client picks a blob the server accepts
+    let mut client = Srd::with_config(false, client_config.clone());
+    let mut server = Srd::with_config(true, config.clone());
+    client.set_blob_provider(Arc::new(FirstAccepted));
+    run_handshake(&mut client, &mut server);
+    assert!(server.get_blob::<BasicBlob>().unwrap().is_some());
+
+    // A server that doesn't list any type gets whatever the client has
+    let mut client = Srd::with_config(false, client_config.clone());
+    let mut server = Srd::with_config(true, client_config.clone());
+    client.set_blob_provider(Arc::new(FirstAccepted));
+    run_handshake(&mut client, &mut server);
+    assert!(server.get_blob::<LogonBlob>().unwrap().is_some());
+
+    // A blob the server would refuse isn't sent
+    let mut client = Srd::with_config(false, client_config.clone());
+    let mut server = Srd::with_config(true, config);
+    client.set_blob(LogonBlob::new("fdubois", "1234567")).unwrap();
+
+    let initiate = send_data(client.step(&[]).unwrap());
+    let offer = send_data(server.step(&initiate).unwrap());
+    let accept = send_data(client.step(&offer).unwrap());
+    let confirm = send_data(server.step(&accept).unwrap());
+    match client.step(&confirm) {
+        Err(SrdError::UnsupportedBlobType(ref blob_type)) => assert_eq!(blob_type, "Logon"),
+        step => panic!("unexpected step {:?}", step),
+    }
+    assert!(client.get_output_data().is_none());
+}